@@ -0,0 +1,350 @@
+//! Two way sync of whole directories using `rclone bisync`. A sync pair links a remote directory to a local directory, by default
+//! its copy in the local mirror. Changes made on either side are copied to the other each time the pair is run, either on demand
+//! or every few minutes when the pair has an interval.
+//!
+//! bisync compares both sides to the listings saved by the previous run, the baseline. The first run of a pair builds the baseline
+//! with `--resync`. Files changed on both sides since the last run are conflicts: bisync keeps both versions and the conflicting
+//! paths are reported. Since rclone 1.66 the versions are renamed `<name>.conflict1` and `<name>.conflict2`, older releases name
+//! them `<name>..path1` and `<name>..path2`.
+//!
+//! When a run fails in a way bisync can only recover from with `--resync`, the pair is marked as needing a resync and its
+//! baseline is kept. A resync copies files missing on either side to the other, bringing back anything deleted since the last
+//! run, so the pair isn't run again, on schedule or on demand, until the user explicitly asks for a resync.
+//!
+//! The pairs of a configuration are saved in `configs/bisync/<config name>/pairs`, one tab separated line per pair, and the
+//! baseline of each pair in the `configs/bisync/<config name>/<pair id>` directory. The last field is true if the pair needs a
+//! resync and is missing from files written before it was added:
+//!
+//! ```
+//! id	interval in minutes	last run	last result	remote path	local path	needs resync
+//! ```
+//!
+//! Loading another configuration while a pair is running doesn't stop it, but its result isn't recorded since the pair belongs
+//! to the configuration that was loaded when it started.
+
+pub mod rustssh {
+use std::fs::{create_dir_all, read_dir, read_to_string, remove_dir_all, write};
+use std::sync::{Arc, Mutex};
+use std::thread::{sleep, spawn};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use crate::*;
+
+/// A remote directory kept in sync with a local directory
+#[derive(Debug, Clone)]
+pub struct SyncPair {
+    pub id: u64,
+    /// Absolute remote path
+    pub remote_path: String,
+    /// Local directory relative to the current directory
+    pub local_path: Remit::SystemPath,
+    /// Minutes between scheduled runs. 0 only runs on demand
+    pub interval: u64,
+    /// When the pair last finished running in seconds since the unix epoch. 0 if it never ran
+    pub last_run: u64,
+    /// ok, conflicts or the error of the last run. Empty if it never ran
+    pub last_result: String,
+    /// True while the pair is running
+    pub running: bool,
+    /// True after a run failed in a way only a resync recovers from. The pair won't run until a resync is requested
+    pub needs_resync: bool
+}
+
+/// Outcome of running a sync pair
+#[derive(Debug, Clone, Serialize)]
+pub struct BisyncResult {
+    /// Id of the pair
+    pub id: u64,
+    pub success: bool,
+    /// Paths changed on both sides since the last run. Both versions are kept
+    pub conflicts: Vec<String>,
+    /// True if the baseline was rebuilt during this run
+    pub resynced: bool,
+    /// True if the run failed and the pair won't run again until a resync is requested
+    pub needs_resync: bool,
+    /// Output of rclone bisync
+    pub output: String
+}
+
+/// Callback receiving the result of every scheduled or on demand run
+pub type BisyncListener = Arc<dyn Fn(BisyncResult) + Send + Sync>;
+
+/// Sync pairs of the current configuration
+pub struct BisyncManager {
+    pairs: Vec<SyncPair>,
+    next_id: u64,
+    /// Directory holding the pairs file and baselines of the configuration
+    dir: Remit::SystemPath,
+    rclone_m: Arc<Mutex<Remit::RCloneManager>>,
+    listener: Option<BisyncListener>
+}
+
+#[allow(dead_code)]
+impl BisyncManager {
+    /// Create a manager with no pairs. Use [`BisyncManager::load`] to load the pairs of a configuration
+    /// # Arguments
+    /// * `rclone_m` - rclone manager used to build the bisync commands
+    pub fn new(rclone_m: Arc<Mutex<Remit::RCloneManager>>) -> BisyncManager {
+        return BisyncManager{pairs: Vec::new(), next_id: 1, dir: Remit::SystemPath::new(), rclone_m: rclone_m, listener: None};
+    }
+
+    /// Get the current time in seconds since the unix epoch
+    fn now() -> u64 {
+        return SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    }
+
+    /// Set the listener that receives the result of every run
+    /// # Arguments
+    /// * `listener` - Called from the thread that ran the pair
+    pub fn set_listener(&mut self, listener: BisyncListener) {
+        self.listener = Some(listener);
+    }
+
+    /// Replace the pairs with the saved pairs of a configuration. A configuration without saved pairs has none
+    /// # Arguments
+    /// * `config_dir` - Directory containing the Remit configurations
+    /// * `config` - Name of the configuration
+    pub fn load(&mut self, config_dir: &Remit::SystemPath, config: &String) -> Result<(), IOError> {
+        let mut dir = config_dir.clone();
+        dir.pushd("bisync".to_string());
+        dir.pushd(config.clone());
+        self.dir = dir;
+        self.pairs.clear();
+        self.next_id = 1;
+        let mut file = self.dir.clone();
+        file.pushd("pairs".to_string());
+        let contents = match read_to_string(file.get_windows_path_local()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == IOErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e)
+        };
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 6 && fields.len() != 7 {
+                continue;
+            }
+            let mut local_path = Remit::SystemPath::new();
+            local_path.set_win_path(fields[5].to_string());
+            let pair = SyncPair{id: fields[0].parse::<u64>().unwrap_or(0), interval: fields[1].parse::<u64>().unwrap_or(0),
+                                last_run: fields[2].parse::<u64>().unwrap_or(0), last_result: fields[3].to_string(),
+                                remote_path: fields[4].to_string(), local_path: local_path, running: false,
+                                needs_resync: fields.get(6) == Some(&"true")};
+            self.next_id = self.next_id.max(pair.id + 1);
+            self.pairs.push(pair);
+        }
+        return Ok(());
+    }
+
+    /// Save the pairs of the current configuration
+    fn save(&self) -> Result<(), IOError> {
+        create_dir_all(self.dir.get_windows_path_local())?;
+        let mut contents = String::new();
+        for pair in &self.pairs {
+            contents += &format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\n", pair.id, pair.interval, pair.last_run, pair.last_result,
+                                    pair.remote_path, pair.local_path.get_windows_path_local(), pair.needs_resync);
+        }
+        let mut file = self.dir.clone();
+        file.pushd("pairs".to_string());
+        return write(file.get_windows_path_local(), contents);
+    }
+
+    /// Get the directory holding the baseline of a pair
+    fn workdir(&self, id: u64) -> Remit::SystemPath {
+        let mut workdir = self.dir.clone();
+        workdir.pushd(id.to_string());
+        return workdir;
+    }
+
+    /// Add a sync pair and return its id. The pair doesn't run until it is due or run on demand
+    /// # Arguments
+    /// * `remote_path` - Absolute remote path of the directory
+    /// * `local_path` - Local directory to keep in sync with it
+    /// * `interval` - Minutes between scheduled runs. 0 only runs on demand
+    pub fn add_pair(&mut self, remote_path: String, local_path: Remit::SystemPath, interval: u64) -> Result<u64, IOError> {
+        if self.pairs.iter().any(|p| p.remote_path == remote_path && p.local_path.get_windows_path_local() == local_path.get_windows_path_local()) {
+            return Err(IOError::new(IOErrorKind::AlreadyExists, "The directories are already synced"));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pairs.push(SyncPair{id: id, remote_path: remote_path, local_path: local_path, interval: interval, last_run: 0,
+                                 last_result: String::new(), running: false, needs_resync: false});
+        self.save()?;
+        return Ok(id);
+    }
+
+    /// Remove a sync pair and its baseline. The directories themselves are left alone
+    /// # Arguments
+    /// * `id` - Id of the pair
+    pub fn remove_pair(&mut self, id: u64) -> Result<(), IOError> {
+        let index = self.pairs.iter().position(|p| p.id == id).ok_or(IOError::new(IOErrorKind::NotFound, "Sync pair not found"))?;
+        if self.pairs[index].running {
+            return Err(IOError::new(IOErrorKind::WouldBlock, "Sync pair is running"));
+        }
+        self.pairs.remove(index);
+        let _r = remove_dir_all(self.workdir(id).get_windows_path_local());
+        return self.save();
+    }
+
+    /// Change how often a pair runs
+    /// # Arguments
+    /// * `id` - Id of the pair
+    /// * `interval` - Minutes between scheduled runs. 0 only runs on demand
+    pub fn set_interval(&mut self, id: u64, interval: u64) -> Result<(), IOError> {
+        self.pairs.iter_mut().find(|p| p.id == id).ok_or(IOError::new(IOErrorKind::NotFound, "Sync pair not found"))?.interval = interval;
+        return self.save();
+    }
+
+    /// Get every sync pair of the current configuration
+    pub fn list(&self) -> Vec<SyncPair> {
+        return self.pairs.clone();
+    }
+
+    /// Run a sync pair. The manager is only locked before and after running rclone, so other pairs can be managed meanwhile.
+    /// Errors without running if the pair needs a resync that wasn't requested. If another configuration was loaded while rclone
+    /// ran, the result isn't recorded and an error is returned instead
+    /// # Arguments
+    /// * `manager` - Manager owning the pair
+    /// * `id` - Id of the pair
+    /// * `resync` - If true the baseline is rebuilt with `--resync`, copying files missing on either side to the other
+    pub fn run(manager: &Arc<Mutex<BisyncManager>>, id: u64, resync: bool) -> Result<BisyncResult, IOError> {
+        let (mut command, resync, dir) = {
+            let mut m = manager.lock().unwrap();
+            m.rclone_m.lock().unwrap().require(Remit::RCloneFeature::Bisync)?;
+            let workdir = m.workdir(id);
+            let dir = m.dir.get_windows_path_local();
+            let pair = m.pairs.iter_mut().find(|p| p.id == id).ok_or(IOError::new(IOErrorKind::NotFound, "Sync pair not found"))?;
+            if pair.running {
+                return Err(IOError::new(IOErrorKind::WouldBlock, "Sync pair is already running"));
+            }
+            if pair.needs_resync && !resync {
+                return Err(IOError::new(IOErrorKind::InvalidData, "Sync pair needs a resync. Check both directories, then run it with resync"));
+            }
+            pair.running = true;
+            let mut remote_path = Remit::SystemPath::new();
+            remote_path.set_path(pair.remote_path.clone());
+            let local_path = pair.local_path.clone();
+            // without saved listings there is no baseline to compare against
+            let resync = resync || read_dir(workdir.get_windows_path_local()).map(|mut entries| entries.next().is_none()).unwrap_or(true);
+            let _r = create_dir_all(local_path.get_windows_path_local());
+            let _r = create_dir_all(workdir.get_windows_path_local());
+            let command = m.rclone_m.lock().unwrap().bisync_command(&local_path, &remote_path, &workdir, resync)?;
+            (command, resync, dir)
+        };
+        let output = command.output();
+
+        let mut m = manager.lock().unwrap();
+        if m.dir.get_windows_path_local() != dir {
+            // the pairs were replaced, an id in the new configuration may belong to a different pair
+            return Err(IOError::new(IOErrorKind::Interrupted, "Another configuration was loaded while the pair ran, its result was not saved"));
+        }
+        let pair = m.pairs.iter_mut().find(|p| p.id == id).ok_or(IOError::new(IOErrorKind::NotFound, "Sync pair not found"))?;
+        pair.running = false;
+        pair.last_run = BisyncManager::now();
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                pair.last_result = e.to_string();
+                let _r = m.save();
+                return Err(e);
+            }
+        };
+        let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+        let success = output.status.success();
+        // bisync refuses to run again until the baseline is rebuilt. The baseline is kept so the user can look into the failure
+        // before choosing to resync
+        pair.needs_resync = if success { false } else { pair.needs_resync || text.contains("--resync") };
+        let result = BisyncResult{id: id, success: success, conflicts: BisyncManager::parse_conflicts(&text),
+                                  resynced: resync, needs_resync: pair.needs_resync, output: text};
+        pair.last_result = if result.needs_resync {
+            "needs resync".to_string()
+        } else if !result.success {
+            "failed".to_string()
+        } else if result.conflicts.len() > 0 {
+            format!("{} conflicts", result.conflicts.len())
+        } else {
+            "ok".to_string()
+        };
+        m.save()?;
+        if let Some(listener) = &m.listener {
+            listener(result.clone());
+        }
+        return Ok(result);
+    }
+
+    /// Get the changes running a sync pair would make to both directories without making them. Changes are reported for both
+    /// directories, with paths relative to the pair's directories. Errors like [`BisyncManager::run`] if the pair is running or
+    /// needs a resync that wasn't requested
+    /// # Arguments
+    /// * `manager` - Manager owning the pair
+    /// * `id` - Id of the pair
+    /// * `resync` - If true the changes a resync would make are listed
+    pub fn plan(manager: &Arc<Mutex<BisyncManager>>, id: u64, resync: bool) -> Result<Vec<Remit::PlannedChange>, IOError> {
+        let mut command = {
+            let m = manager.lock().unwrap();
+            let workdir = m.workdir(id);
+            let pair = m.pairs.iter().find(|p| p.id == id).ok_or(IOError::new(IOErrorKind::NotFound, "Sync pair not found"))?;
+            if pair.running {
+                // bisync locks the pair's listings while it runs
+                return Err(IOError::new(IOErrorKind::WouldBlock, "Sync pair is running"));
+            }
+            if pair.needs_resync && !resync {
+                return Err(IOError::new(IOErrorKind::InvalidData, "Sync pair needs a resync. Check both directories, then preview it with resync"));
+            }
+            let mut remote_path = Remit::SystemPath::new();
+            remote_path.set_path(pair.remote_path.clone());
+            let resync = resync || read_dir(workdir.get_windows_path_local()).map(|mut entries| entries.next().is_none()).unwrap_or(true);
+            let _r = create_dir_all(workdir.get_windows_path_local());
+            let rclone = m.rclone_m.lock().unwrap();
+            rclone.require(Remit::RCloneFeature::JsonLog)?;
+            let mut command = rclone.bisync_command(&pair.local_path, &remote_path, &workdir, resync)?;
+            command.arg("--dry-run").arg("--use-json-log");
+            command
+        };
+        return Remit::RCloneManager::plan_result(command.output()?);
+    }
+
+    /// Get the paths bisync reported as changed on both sides
+    /// # Arguments
+    /// * `output` - Output of rclone bisync
+    fn parse_conflicts(output: &str) -> Vec<String> {
+        let mut conflicts: Vec<String> = Vec::new();
+        for line in output.lines() {
+            if !line.contains("New or changed in both paths") {
+                continue;
+            }
+            if let Some((_, path)) = line.rsplit_once(" - ") {
+                let path = path.trim().to_string();
+                if !conflicts.contains(&path) {
+                    conflicts.push(path);
+                }
+            }
+        }
+        return conflicts;
+    }
+
+    /// Start the thread that runs pairs when their interval has passed. Due pairs are checked every 30 seconds. Pairs that need a
+    /// resync are skipped
+    /// # Arguments
+    /// * `manager` - Manager owning the pairs
+    pub fn start(manager: &Arc<Mutex<BisyncManager>>) {
+        let manager = manager.clone();
+        spawn(move || {
+            loop {
+                let now = BisyncManager::now();
+                let due: Vec<u64> = match manager.lock() {
+                    Ok(m) => m.pairs.iter().filter(|p| p.interval > 0 && !p.running && !p.needs_resync && p.last_run + p.interval * 60 <= now)
+                                            .map(|p| p.id).collect(),
+                    Err(_) => return
+                };
+                for id in due {
+                    if let Err(e) = BisyncManager::run(&manager, id, false) {
+                        println!("scheduled sync of pair {} failed: {}", id, e);
+                    }
+                }
+                sleep(Duration::from_secs(30));
+            }
+        });
+    }
+}
+
+}
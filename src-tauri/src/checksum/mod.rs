@@ -0,0 +1,60 @@
+//! This module compares the local mirror of a remote file or directory against the server. Hashes are computed on the remote machine with
+//! `sha256sum` (or `md5sum` when sha256sum is not installed) and locally with `rclone hashsum` using the same algorithm. Both commands output
+//! one `hash  path` line per file which are parsed and matched by their path relative to the compared directory.
+
+pub mod rustssh {
+use std::collections::BTreeMap;
+
+/// Result of comparing a single file in the local mirror with the remote server
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncStatus {
+    /// Both copies have the same hash
+    Match,
+    /// Both copies exist but their hashes differ
+    Differ,
+    /// The file only exists on the remote server
+    MissingLocal,
+    /// The file only exists in the local mirror
+    MissingRemote
+}
+
+/// Parse the output of `sha256sum`, `md5sum` or `rclone hashsum` into a map of hashes stored by path. Leading `./` and the `*` binary
+/// mode marker are removed from paths
+/// # Arguments
+/// * `output` - Output of the hashing command
+pub fn parse_hashes(output: &str) -> BTreeMap<String, String> {
+    let mut hashes: BTreeMap<String, String> = BTreeMap::new();
+    for line in output.lines() {
+        let mut parts = line.splitn(2, ' ');
+        let hash = parts.next().unwrap_or("");
+        let path = parts.next().unwrap_or("").trim_start_matches(' ').trim_start_matches('*');
+        if hash.len() == 0 || path.len() == 0 {
+            continue;
+        }
+        hashes.insert(path.trim_start_matches("./").to_string(), hash.to_lowercase());
+    }
+    return hashes;
+}
+
+/// Compare 2 sets of hashes stored by relative path
+/// # Arguments
+/// * `local` - Hashes of the files in the local mirror
+/// * `remote` - Hashes of the files on the remote server
+pub fn compare(local: &BTreeMap<String, String>, remote: &BTreeMap<String, String>) -> BTreeMap<String, SyncStatus> {
+    let mut results: BTreeMap<String, SyncStatus> = BTreeMap::new();
+    for (path, hash) in remote {
+        match local.get(path) {
+            Some(local_hash) if local_hash == hash => results.insert(path.clone(), SyncStatus::Match),
+            Some(_) => results.insert(path.clone(), SyncStatus::Differ),
+            None => results.insert(path.clone(), SyncStatus::MissingLocal)
+        };
+    }
+    for path in local.keys() {
+        if !remote.contains_key(path) {
+            results.insert(path.clone(), SyncStatus::MissingRemote);
+        }
+    }
+    return results;
+}
+
+}
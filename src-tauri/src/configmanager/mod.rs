@@ -9,19 +9,77 @@
 //! host the host
 //! port portasnumber
 //! username ssh username 
+//! backend sftp|s3|webdav|ftp|local
+//! sort name|size|type
+//! sort_descending true|false
+//! dirs_first true|false
+//! show_hidden true|false
+//! trash true|false
+//! trash_retention_days days
+//! verify_transfers true|false
+//! verify_retries count
+//! bwlimit rate or timetable
+//! transfers count
+//! checkers count
+//! ignore pattern
+//! bookmark name<tab>remote path
 //! ```
 //! 
+//! The backend line is the type of the rclone configuration and defaults to sftp. Configurations of other backends have no ssh
+//! session, their host holds the server or url they connect to if they have one and the password line is empty.
+//! 
+//! The sort, sort_descending, dirs_first and show_hidden lines are the default listing options for the configuration. They are optional
+//! and files without them list every file sorted by name. The trash lines control whether deletions are moved to a trash directory on the
+//! server and how many days they are kept there. A retention of 0 keeps trashed files until they are purged. The verify lines
+//! control whether transferred files are checked against their source and how many times a file that doesn't match is copied again. The bwlimit line is
+//! passed to rclone's --bwlimit, either a single rate like 512k or a timetable like `08:00,512k 19:00,off`, and transfers and checkers
+//! set how many files rclone copies and checks in parallel, 0 leaving rclone's default. Each ignore line holds one gitignore style
+//! pattern of files that are never uploaded or synced, see [`crate::ignorerules::rustssh::IgnoreRules`]. There is one bookmark line for
+//! each saved remote directory, with the bookmark name and path separated by a tab.
+//! 
+//! Recently visited directories and recently opened files are kept separately for each configuration in `<name>.history` next to its
+//! configuration file. Each line is a tab separated entry, most recent first, and each list is capped at [`RECENT_LIMIT`] entries:
+//! 
+//! ```
+//! dir|file	seconds since the unix epoch	remote path
+//! ```
+//! 
+//! The path of the rclone executable chosen by the user is shared by every configuration and kept on the only line of `rclone.path`.
+//! Without it rclone is looked for next to Remit and on the PATH.
+//! 
 //! The configuration manager will store these configurations under the configs folder in the local directory. This is currently hardcoded but will most likely
 //! change to allow users to better manager their configuration files.
 
 pub mod rustssh {
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::read_dir;
 use std::fs::read_to_string;
 use std::fs::write;
 use std::fs::create_dir_all;
+use std::time::{SystemTime, UNIX_EPOCH};
 use crate::*;
 
+/// Maximum number of directories and of files kept in a [`RecentHistory`]
+pub const RECENT_LIMIT: usize = 25;
+
+/// Days trashed files are kept when a configuration doesn't say otherwise
+pub const TRASH_RETENTION_DAYS: u64 = 30;
+
+/// A remote path and when it was last visited or opened
+#[derive(Clone)]
+pub struct RecentPath {
+    pub path: String,
+    /// Seconds since the unix epoch
+    pub time: u64
+}
+
+/// Recently visited directories and recently opened files of a configuration, most recent first
+#[derive(Clone)]
+pub struct RecentHistory {
+    pub dirs: Vec<RecentPath>,
+    pub files: Vec<RecentPath>
+}
+
 /// Contains the information of a Remit configuration file. Since Remit currently only supports username/password auth,
 /// the information in this struct is all that's needed to connect to a server
 #[derive(Clone)]
@@ -32,6 +90,33 @@ pub struct RemitConfig {
     pub name: String,
     pub port: String,
 
+    /// rclone backend of the configuration e.g. sftp or s3. Only sftp configurations are browsed over ssh
+    pub backend: String,
+
+    /// Default sorting and filtering for directory listings. Only the sort key, direction, dirs_first and show_hidden are saved
+    pub listing: Remit::ListingOptions,
+
+    /// If true, deleted files are moved into the remote trash instead of being removed
+    pub trash: bool,
+
+    /// Number of days to keep items in the remote trash. 0 keeps them until purged
+    pub trash_retention_days: u64,
+
+    /// If true, transferred files are checked against their source after copying
+    pub verify_transfers: bool,
+
+    /// Number of times a file that fails verification is copied again
+    pub verify_retries: u32,
+
+    /// Bandwidth and concurrency limits for rclone
+    pub limits: Remit::TransferLimits,
+
+    /// gitignore style patterns of files that are never transferred
+    pub ignore: Vec<String>,
+
+    /// Remote directory paths stored by bookmark name
+    pub bookmarks: BTreeMap<String, String>,
+
     /// This is the path from the local directory to the config file location. **Not currently used**
     pub path: Remit::SystemPath
 }
@@ -94,9 +179,30 @@ impl ConfigManager {
             match args.next().unwrap() {
                 "username"=>config.username.push_str(&line[9..line.len()]),
                 "password"=>config.password.push_str(&line[9..line.len()]),
-                "host"=>config.host.push_str(args.next().unwrap()),
+                "host"=>config.host.push_str(args.next().unwrap_or("")),
                 "name"=>config.name.push_str(&line[5..line.len()]),
-                "port"=>config.port.push_str(args.next().unwrap()),
+                "port"=>config.port.push_str(args.next().unwrap_or("")),
+                "backend"=>config.backend = args.next().unwrap_or("sftp").to_string(),
+                "sort"=>config.listing.sort_key = Remit::SortKey::parse(args.next().unwrap_or("")).unwrap_or(Remit::SortKey::Name),
+                "sort_descending"=>config.listing.descending = args.next() == Some("true"),
+                "dirs_first"=>config.listing.dirs_first = args.next() == Some("true"),
+                "show_hidden"=>config.listing.show_hidden = args.next() != Some("false"),
+                "trash"=>config.trash = args.next() == Some("true"),
+                "trash_retention_days"=>config.trash_retention_days = args.next().and_then(|d| d.parse::<u64>().ok()).unwrap_or(TRASH_RETENTION_DAYS),
+                "verify_transfers"=>config.verify_transfers = args.next() == Some("true"),
+                "verify_retries"=>config.verify_retries = args.next().unwrap_or("1").parse::<u32>().unwrap_or(1),
+                "bwlimit"=>config.limits.bwlimit = line[7..line.len()].trim().to_string(),
+                "transfers"=>config.limits.transfers = args.next().unwrap_or("0").parse::<u32>().unwrap_or(0),
+                "checkers"=>config.limits.checkers = args.next().unwrap_or("0").parse::<u32>().unwrap_or(0),
+                "ignore"=>config.ignore.push(line[7..line.len()].trim().to_string()),
+                "bookmark"=>{
+                    let mut bookmark = line[9..line.len()].splitn(2, '\t');
+                    let name = bookmark.next().unwrap_or("").to_string();
+                    let path = bookmark.next().unwrap_or("").to_string();
+                    if name.len() > 0 && path.len() > 0 {
+                        config.bookmarks.insert(name, path);
+                    }
+                },
                 _=>{}
             }
         }
@@ -120,13 +226,96 @@ impl ConfigManager {
         }
         let c = self.configs.get(name).unwrap();
         let full_path = self.config_path.get_path() + "/" + name + ".rcfg";
-        let contents = format!("username {}\npassword {}\nhost {}\nname {}\nport {}\n", c.username, c.password, c.host, c.name, c.port);
+        let mut contents = format!("username {}\npassword {}\nhost {}\nname {}\nport {}\n", c.username, c.password, c.host, c.name, c.port);
+        contents += &format!("backend {}\n", c.backend);
+        contents += &format!("sort {}\nsort_descending {}\ndirs_first {}\nshow_hidden {}\n", c.listing.sort_key.to_string(),
+                                c.listing.descending, c.listing.dirs_first, c.listing.show_hidden);
+        contents += &format!("trash {}\ntrash_retention_days {}\n", c.trash, c.trash_retention_days);
+        contents += &format!("verify_transfers {}\nverify_retries {}\n", c.verify_transfers, c.verify_retries);
+        contents += &format!("bwlimit {}\ntransfers {}\ncheckers {}\n", c.limits.bwlimit, c.limits.transfers, c.limits.checkers);
+        for pattern in &c.ignore {
+            contents += &format!("ignore {}\n", pattern);
+        }
+        for (bookmark, path) in &c.bookmarks {
+            contents += &format!("bookmark {}\t{}\n", bookmark, path);
+        }
         match write(full_path, contents) {
             Ok(_)=>return Ok(()),
             Err(e)=>return Err(e)
         }
     }
 
+    /// Get the path of the rclone executable chosen by the user, if there is one
+    pub fn rclone_path(&self) -> Option<String> {
+        let contents = read_to_string(self.config_path.get_path() + "/rclone.path").ok()?;
+        let path = contents.trim();
+        if path.len() == 0 {
+            return None;
+        }
+        return Some(path.to_string());
+    }
+
+    /// Save the path of the rclone executable. None removes it, so rclone is looked for again
+    /// # Arguments
+    /// * `path` - Path of the rclone executable or of the directory containing it
+    pub fn save_rclone_path(&self, path: Option<&String>) -> Result<(), IOError> {
+        return write(self.config_path.get_path() + "/rclone.path", path.cloned().unwrap_or_default());
+    }
+
+    /// Get the directory containing the configuration files
+    pub fn config_dir(&self) -> Remit::SystemPath {
+        return self.config_path.clone();
+    }
+
+    /// Get a copy of a configuration by name
+    /// # Arguments
+    /// * `name` - Name of the configuration
+    pub fn get_config(&self, name: &str) -> Option<RemitConfig> {
+        return self.configs.get(name).cloned();
+    }
+
+    /// Load the recent history of a configuration. A missing history file gives an empty history
+    /// # Arguments
+    /// * `name` - Name of the configuration
+    pub fn load_history(&self, name: &str) -> Result<RecentHistory, IOError> {
+        let mut history = RecentHistory::new();
+        let contents = match read_to_string(self.config_path.get_path() + "/" + name + ".history") {
+            Ok(contents)=>contents,
+            Err(e) if e.kind() == IOErrorKind::NotFound=>return Ok(history),
+            Err(e)=>return Err(e)
+        };
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.splitn(3, '\t').collect();
+            if fields.len() != 3 {
+                continue;
+            }
+            let recent = RecentPath{path: fields[2].to_string(), time: fields[1].parse::<u64>().unwrap_or(0)};
+            match fields[0] {
+                "dir"=>history.dirs.push(recent),
+                "file"=>history.files.push(recent),
+                _=>{}
+            }
+        }
+        history.dirs.truncate(RECENT_LIMIT);
+        history.files.truncate(RECENT_LIMIT);
+        return Ok(history);
+    }
+
+    /// Save the recent history of a configuration, replacing the previous history file
+    /// # Arguments
+    /// * `name` - Name of the configuration
+    /// * `history` - History to save
+    pub fn save_history(&self, name: &str, history: &RecentHistory) -> Result<(), IOError> {
+        let mut contents = String::new();
+        for recent in &history.dirs {
+            contents += &format!("dir\t{}\t{}\n", recent.time, recent.path);
+        }
+        for recent in &history.files {
+            contents += &format!("file\t{}\t{}\n", recent.time, recent.path);
+        }
+        return write(self.config_path.get_path() + "/" + name + ".history", contents);
+    }
+
     /// Get a vector of all [`RemitConfig`] files in the manager's map
     pub fn get_configs(&mut self) -> Vec<RemitConfig>{
         let mut configs = Vec::<RemitConfig>::new();
@@ -137,12 +326,65 @@ impl ConfigManager {
     }
 }
 
+#[allow(dead_code)]
+impl RecentHistory {
+    /// Create an empty history
+    pub fn new() -> RecentHistory {
+        return RecentHistory{dirs: Vec::new(), files: Vec::new()};
+    }
+
+    /// Move a path to the front of a list with the current time and drop the oldest entries past [`RECENT_LIMIT`]
+    /// # Arguments
+    /// * `list` - List to add to
+    /// * `path` - Remote path
+    fn push(list: &mut Vec<RecentPath>, path: String) {
+        list.retain(|recent| recent.path != path);
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        list.insert(0, RecentPath{path: path, time: time});
+        list.truncate(RECENT_LIMIT);
+    }
+
+    /// Record a visited directory
+    /// # Arguments
+    /// * `path` - Absolute remote path of the directory
+    pub fn push_dir(&mut self, path: String) {
+        RecentHistory::push(&mut self.dirs, path);
+    }
+
+    /// Record an opened file
+    /// # Arguments
+    /// * `path` - Absolute remote path of the file
+    pub fn push_file(&mut self, path: String) {
+        RecentHistory::push(&mut self.files, path);
+    }
+
+    /// Get the most recently visited directory
+    pub fn last_dir(&self) -> Option<String> {
+        return self.dirs.first().map(|recent| recent.path.clone());
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.dirs.clear();
+        self.files.clear();
+    }
+}
+
 impl RemitConfig {
     /// Create an empty [`RemitConfig`] object
     pub fn new() -> RemitConfig{
         return RemitConfig{username: String::new(), password: String::new(),
                                 host: String::new(), name: String::new(),
                                 port: String::new(),
+                                backend: "sftp".to_string(),
+                                listing: Remit::ListingOptions::new(),
+                                trash: false,
+                                trash_retention_days: TRASH_RETENTION_DAYS,
+                                verify_transfers: false,
+                                verify_retries: 1,
+                                limits: Remit::TransferLimits::new(),
+                                ignore: Vec::new(),
+                                bookmarks: BTreeMap::new(),
                                 path: Remit::SystemPath::new()};
     }
 }
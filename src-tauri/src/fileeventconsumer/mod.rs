@@ -11,15 +11,33 @@ pub mod rustssh {
     //! 
     //! Currently, [`FileEventConsumer`] only processes file modification events. It ignores deletions and creations. These are expected
     //! to be handled directly through the gui application and so the events reported by Windows can be ignored
+    //! 
+    //! Files matching the configuration's ignore patterns or a `.remitignore` file in the tracked directory are never uploaded.
+    //! See [`crate::ignorerules::rustssh::IgnoreRules`]
 
 
 
+    #[cfg(windows)]
     use windows::Win32::Storage::FileSystem::*;
+    #[cfg(not(windows))]
+    use self::file_actions::*;
     use std::sync::{Arc, Mutex};
     use std::thread::*;
     use std::fs::metadata;
     use crate::*;
 
+    /// The Win32 file actions for platforms without the Win32 API, so events can still be built and processed there
+    #[cfg(not(windows))]
+    #[allow(non_camel_case_types, dead_code)]
+    mod file_actions {
+        pub type FILE_ACTION = u32;
+        pub const FILE_ACTION_ADDED: FILE_ACTION = 1;
+        pub const FILE_ACTION_REMOVED: FILE_ACTION = 2;
+        pub const FILE_ACTION_MODIFIED: FILE_ACTION = 3;
+        pub const FILE_ACTION_RENAMED_OLD_NAME: FILE_ACTION = 4;
+        pub const FILE_ACTION_RENAMED_NEW_NAME: FILE_ACTION = 5;
+    }
+
     /// Contains information from a Win32 [ReadDirectoryW](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-readdirectorychangesw)
     /// api alert
     pub struct FileEvent {
@@ -30,7 +48,10 @@ pub mod rustssh {
         remote_file_path: String,
 
         /// Local path to the affected file
-        local_file_path: String
+        local_file_path: String,
+
+        /// Local path of the tracked directory containing the file
+        local_root: String
     }
 
     impl FileEvent {
@@ -39,8 +60,15 @@ pub mod rustssh {
         /// * `event_type` - A Win32 FILE_ACTION
         /// * `local_path` - Path to the file on the local computer
         /// * `remote_path` - Path on the remote system
-        pub fn new(event_type: FILE_ACTION, local_path: String, remote_path: String) -> FileEvent {
-            return FileEvent{event_type: event_type, local_file_path: local_path, remote_file_path: remote_path};
+        /// * `local_root` - Path of the tracked directory the local path is in
+        pub fn new(event_type: FILE_ACTION, local_path: String, remote_path: String, local_root: String) -> FileEvent {
+            return FileEvent{event_type: event_type, local_file_path: local_path, remote_file_path: remote_path, local_root: local_root};
+        }
+
+        /// Get the local path relative to the tracked directory, separated by /
+        fn relative_path(&self) -> String {
+            let relative = self.local_file_path.strip_prefix(&self.local_root).unwrap_or(&self.local_file_path);
+            return relative.trim_start_matches('\\').replace("\\", "/");
         }
     }
 
@@ -88,6 +116,12 @@ pub mod rustssh {
         // through the GUI.
         match event.event_type {
             FILE_ACTION_ADDED | FILE_ACTION_MODIFIED=> {
+                let mut root = Remit::SystemPath::new();
+                root.set_win_path(event.local_root.clone());
+                if instance.lock().unwrap().is_ignored(&root, &event.relative_path()) {
+                    println!("ignoring {}", event.local_file_path);
+                    return;
+                }
                 let mut local_dir = Remit::Directory::new(None);
                 let mut remote_dir = Remit::Directory::new(None);
                 local_dir.path.set_win_path(format!("\\{}", event.local_file_path.clone()));
@@ -0,0 +1,271 @@
+//! This module builds quick previews of remote files. A preview is created from the first bytes of a file read over sftp, so a file can be
+//! looked at without syncing it into the local mirror or starting change tracking.
+//! 
+//! The kind of file is decided by looking at its contents rather than its extension. Known image signatures (png, jpeg, gif, bmp, webp, ico)
+//! are reported as images. Anything else is treated as text if it can be decoded, and as binary otherwise. Text is decoded as UTF-8 or
+//! UTF-16 when a byte order mark is present, falling back to Latin-1.
+
+pub mod rustssh {
+
+/// The kind of content found in a file
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreviewKind {
+    /// Decodable text
+    Text,
+    /// A recognised image format
+    Image,
+    /// Anything that isn't text or a known image
+    Binary
+}
+
+/// A preview of the start of a remote file
+#[derive(Debug, Clone)]
+pub struct FilePreview {
+    /// What the content was detected as
+    pub kind: PreviewKind,
+    /// Mime type for images e.g. image/png. Empty for other kinds
+    pub mime: String,
+    /// Name of the detected text encoding. Empty for other kinds
+    pub encoding: String,
+    /// Decoded text for text files or base64 encoded bytes for images. Empty for binary files and images too large to preview
+    pub content: String,
+    /// Size of the whole file in bytes
+    pub size: u64,
+    /// True if only part of the file was read
+    pub truncated: bool
+}
+
+impl FilePreview {
+    /// Build a preview from the bytes read from the start of a file
+    /// # Arguments
+    /// * `bytes` - Bytes read from the start of the file
+    /// * `size` - Size of the whole file in bytes
+    pub fn new(bytes: &[u8], size: u64) -> FilePreview {
+        let mut preview = FilePreview{kind: PreviewKind::Binary, mime: String::new(), encoding: String::new(),
+                                      content: String::new(), size: size, truncated: (bytes.len() as u64) < size};
+        if let Some(mime) = image_mime(bytes, size) {
+            preview.kind = PreviewKind::Image;
+            preview.mime = mime.to_string();
+            // a partial image can't be displayed
+            if !preview.truncated {
+                preview.content = base64_encode(bytes);
+            }
+            return preview;
+        }
+        if let Some((text, encoding)) = decode_text(bytes) {
+            preview.kind = PreviewKind::Text;
+            preview.encoding = encoding.to_string();
+            preview.content = text;
+        }
+        return preview;
+    }
+}
+
+/// Check the start of a file against known image signatures
+/// # Arguments
+/// * `bytes` - Start of the file
+/// * `size` - Size of the whole file in bytes
+pub fn image_mime(bytes: &[u8], size: u64) -> Option<&'static str> {
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return Some("image/png");
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some("image/jpeg");
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some("image/gif");
+    } else if is_bmp(bytes, size) {
+        return Some("image/bmp");
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    } else if bytes.starts_with(&[0x00, 0x00, 0x01, 0x00]) {
+        return Some("image/x-icon");
+    }
+    return None;
+}
+
+/// Check for a BITMAPFILEHEADER. Its signature is only "BM", so the file size it records and its reserved bytes, which are always zero,
+/// are checked too so text files starting with BM aren't reported as images
+/// # Arguments
+/// * `bytes` - Start of the file
+/// * `size` - Size of the whole file in bytes
+fn is_bmp(bytes: &[u8], size: u64) -> bool {
+    if bytes.len() < 14 || !bytes.starts_with(b"BM") {
+        return false;
+    }
+    let recorded = u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]) as u64;
+    return recorded == size && bytes[6..10].iter().all(|b| *b == 0);
+}
+
+/// Check if the bytes look like binary data. Text files practically never contain a NUL byte
+/// # Arguments
+/// * `bytes` - Bytes to check
+pub fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return false;
+    }
+    return bytes.contains(&0u8);
+}
+
+/// Decode bytes into text, returning the text and the name of the encoding used. Returns None if the bytes are binary
+/// 
+/// Since the bytes may be cut off in the middle of a character, an incomplete character at the end is dropped
+/// # Arguments
+/// * `bytes` - Bytes to decode
+pub fn decode_text(bytes: &[u8]) -> Option<(String, &'static str)> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Some((decode_utf8(&bytes[3..]).unwrap_or(String::from_utf8_lossy(&bytes[3..]).to_string()), "utf-8"));
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Some((decode_utf16(&bytes[2..], true), "utf-16le"));
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some((decode_utf16(&bytes[2..], false), "utf-16be"));
+    }
+    if is_binary(bytes) {
+        return None;
+    }
+    if let Some(text) = decode_utf8(bytes) {
+        return Some((text, "utf-8"));
+    }
+    // every byte is a valid Latin-1 character
+    return Some((bytes.iter().map(|b| *b as char).collect(), "iso-8859-1"));
+}
+
+/// Decode a whole file for editing, returning the text and the encoding it must be written back with. Returns None if the bytes are
+/// binary or can't be decoded without losing anything, so saving the text can't change bytes the user didn't edit
+/// 
+/// The encodings are utf-8, utf-8-bom, utf-16le and utf-16be, which both start with a byte order mark, and iso-8859-1.
+/// See [`encode_text`]
+/// # Arguments
+/// * `bytes` - Every byte of the file
+pub fn decode_exact(bytes: &[u8]) -> Option<(String, &'static str)> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return std::str::from_utf8(&bytes[3..]).ok().map(|text| (text.to_string(), "utf-8-bom"));
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        let little_endian = bytes[0] == 0xFF;
+        let units: Vec<u16> = bytes[2..].chunks(2).map(|pair| {
+            if pair.len() < 2 {
+                // an odd number of bytes can't be written back
+                return None;
+            } else if little_endian {
+                return Some(u16::from_le_bytes([pair[0], pair[1]]));
+            }
+            return Some(u16::from_be_bytes([pair[0], pair[1]]));
+        }).collect::<Option<Vec<u16>>>()?;
+        let text = String::from_utf16(&units).ok()?;
+        return Some((text, if little_endian { "utf-16le" } else { "utf-16be" }));
+    }
+    if is_binary(bytes) {
+        return None;
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Some((text.to_string(), "utf-8"));
+    }
+    return Some((bytes.iter().map(|b| *b as char).collect(), "iso-8859-1"));
+}
+
+/// Encode text in an encoding returned by [`decode_exact`], including its byte order mark. Returns None if the encoding is unknown
+/// or the text contains characters it can't represent
+/// # Arguments
+/// * `text` - Text to encode
+/// * `encoding` - Name of the encoding
+pub fn encode_text(text: &str, encoding: &str) -> Option<Vec<u8>> {
+    match encoding {
+        "utf-8" => return Some(text.as_bytes().to_vec()),
+        "utf-8-bom" => return Some([&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat()),
+        "utf-16le" => return Some([0xFF, 0xFE].into_iter().chain(text.encode_utf16().flat_map(|u| u.to_le_bytes())).collect()),
+        "utf-16be" => return Some([0xFE, 0xFF].into_iter().chain(text.encode_utf16().flat_map(|u| u.to_be_bytes())).collect()),
+        "iso-8859-1" => return text.chars().map(|c| u8::try_from(c as u32).ok()).collect(),
+        _ => return None
+    }
+}
+
+/// Decode UTF-8 allowing an incomplete character at the end
+/// # Arguments
+/// * `bytes` - Bytes to decode
+fn decode_utf8(bytes: &[u8]) -> Option<String> {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => return Some(s.to_string()),
+        // error_len is None when the input ends in the middle of a character
+        Err(e) if e.error_len().is_none() => return Some(String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string()),
+        Err(_) => return None
+    }
+}
+
+/// Decode UTF-16 replacing invalid characters
+/// # Arguments
+/// * `bytes` - Bytes to decode without the byte order mark
+/// * `little_endian` - Byte order of the text
+fn decode_utf16(bytes: &[u8], little_endian: bool) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| {
+        if little_endian {
+            return u16::from_le_bytes([pair[0], pair[1]]);
+        }
+        return u16::from_be_bytes([pair[0], pair[1]]);
+    }).collect();
+    return String::from_utf16_lossy(&units);
+}
+
+/// Encode bytes using the standard base64 alphabet with padding
+/// # Arguments
+/// * `bytes` - Bytes to encode
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        output.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        output.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        if chunk.len() > 1 {
+            output.push(ALPHABET[(n >> 6) as usize & 63] as char);
+        } else {
+            output.push('=');
+        }
+        if chunk.len() > 2 {
+            output.push(ALPHABET[n as usize & 63] as char);
+        } else {
+            output.push('=');
+        }
+    }
+    return output;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bmp_needs_a_matching_header() {
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&30u32.to_le_bytes());
+        bmp.extend_from_slice(&[0, 0, 0, 0, 26, 0, 0, 0]);
+        bmp.resize(30, 0);
+        assert_eq!(image_mime(&bmp, 30), Some("image/bmp"));
+        assert_eq!(image_mime(&bmp, 31), None);
+        assert_eq!(image_mime(b"BMI notes for the week\n", 23), None);
+    }
+
+    #[test]
+    fn exact_decoding_round_trips() {
+        let files: Vec<Vec<u8>> = vec![b"plain text\n".to_vec(),
+                                       [&[0xEF, 0xBB, 0xBF][..], "caf\u{e9}".as_bytes()].concat(),
+                                       vec![0xFF, 0xFE, b'h', 0, b'i', 0],
+                                       vec![0xFE, 0xFF, 0, b'h', 0, b'i'],
+                                       vec![b'c', b'a', b'f', 0xE9]];
+        for bytes in files {
+            let (text, encoding) = decode_exact(&bytes).unwrap();
+            assert_eq!(encode_text(&text, encoding), Some(bytes));
+        }
+    }
+
+    #[test]
+    fn exact_decoding_refuses_lossy_files() {
+        assert_eq!(decode_exact(&[0xEF, 0xBB, 0xBF, b'a', 0xC3]), None);
+        assert_eq!(decode_exact(&[0xFF, 0xFE, b'a', 0, b'b']), None);
+        assert_eq!(decode_exact(&[b'a', 0, b'b']), None);
+        assert_eq!(encode_text("\u{20ac}", "iso-8859-1"), None);
+    }
+}
+
+}
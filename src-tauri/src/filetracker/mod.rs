@@ -6,17 +6,26 @@
 //! starts the consumer and creates a thread to track changers. Conversely, the stop tracking pauses the consumer thread and kills
 //! the tracking thread. It's important to start tracking before calling the stop tracking as doing so can result in 2 threads running at the same time 
 //! producing duplicate events.
+//! 
+//! Tracking needs the Win32 API. On other platforms the tracker builds but [`rustssh::DirectoryTracker::start_tracking`] only logs that
+//! changes aren't tracked, so local edits have to be uploaded by hand.
 
 pub mod rustssh {
+    #[cfg(windows)]
     #[allow(unused_imports,dead_code)]
     use windows::Win32::Storage::FileSystem::*;
+    #[cfg(windows)]
     use windows::Win32::System::Threading::CreateEventA;
 
+    #[cfg(windows)]
     use windows::Win32::System::IO::{OVERLAPPED, GetOverlappedResult};
+    #[cfg(windows)]
     use windows::Win32::Foundation::{HANDLE, INVALID_HANDLE_VALUE, GetLastError, PSTR};
     use std::thread::spawn;
     use std::sync::{Arc, Mutex};
+    #[cfg(windows)]
     use std::env::current_dir;
+    #[cfg(windows)]
     use std::fs::create_dir_all;
     use crate::*;
 
@@ -30,10 +39,22 @@ pub mod rustssh {
         thread_control: Arc<Mutex::<ThreadStatus>>,
         
         /// The retrieved handle to the directory
+        #[cfg(windows)]
         dir_handle: Arc<HANDLE>,
 
         /// The consumer for the file events
-        consumer: Arc::<Mutex::<Remit::FileEventConsumer>>
+        consumer: Arc::<Mutex::<Remit::FileEventConsumer>>,
+
+        /// Remote directory the tracked path mirrors as a relative windows path ending in \\ e.g. home\\user\\project\\. Empty for the
+        /// local mirror, where the path under the tracked directory is already the remote path
+        remote_root: Arc::<Mutex::<String>>,
+
+        /// Shared rclone manager, used to wait for a download job before tracking
+        rclone_instance: Arc::<Mutex::<Remit::RCloneManager>>,
+
+        /// Remote directories, as relative windows paths like [`DirectoryTracker::remote_root`], whose changes are skipped while a
+        /// daemon job writes into them
+        skipped: Arc::<Mutex::<Vec<String>>>
     }
 
     impl DirectoryTracker {
@@ -44,8 +65,23 @@ pub mod rustssh {
         pub fn new(path: Remit::SystemPath, rclone_instance: Arc::<Mutex::<Remit::RCloneManager>>) -> DirectoryTracker {
             return DirectoryTracker{path: Arc::new(Mutex::new(path)),
                                     thread_control: Arc::new(Mutex::new(ThreadStatus::Resume)),
+                                    #[cfg(windows)]
                                     dir_handle: Arc::new(INVALID_HANDLE_VALUE),
-                                    consumer: Arc::new(Mutex::new(Remit::FileEventConsumer::new(rclone_instance.clone())))};
+                                    consumer: Arc::new(Mutex::new(Remit::FileEventConsumer::new(rclone_instance.clone()))),
+                                    remote_root: Arc::new(Mutex::new(String::new())),
+                                    rclone_instance: rclone_instance.clone(),
+                                    skipped: Arc::new(Mutex::new(Vec::new()))};
+        }
+
+        /// Set the remote directory that the tracked directory is a copy of. Needed when tracking a folder outside the local mirror
+        /// # Arguments
+        /// * `remote_root` - Absolute remote path e.g. /home/user/project. An empty path means the tracked directory is the local mirror
+        pub fn set_remote_root(&mut self, remote_root: String) {
+            let mut windows_root = remote_root.trim_start_matches('/').replace("/", "\\");
+            if windows_root.len() > 0 && !windows_root.ends_with('\\') {
+                windows_root.push('\\');
+            }
+            *self.remote_root.lock().unwrap() = windows_root;
         }
 
         /// This function sets the directory tracking thread status to kill. Then it pauses the consumer and clears all waiting events. 
@@ -61,6 +97,7 @@ pub mod rustssh {
         /// to a utf8 String
         /// # Arguments
         /// * `obj` - A Win32 FILE_NOTIFY_INFORMATION for the FileName field to be extracted
+        #[cfg(windows)]
         fn filename_from_notify_obj(obj: &FILE_NOTIFY_INFORMATION) -> String{
             let mut buffer: Vec::<u16> = Vec::new();
             let buffer_length = obj.FileNameLength/2;
@@ -82,6 +119,7 @@ pub mod rustssh {
         /// Paths are only valid for windows as the formatting is ({}\\{}, current_directory, relative_windows_path)
         /// # Arguments
         /// * `path` - Path to track. Will be created if necessary
+        #[cfg(windows)]
         fn set_dir_handle(&mut self, path: &Remit::SystemPath) -> Result<(), IOError> {
             // build absolute path on windows
             let track_path = format!("{}\\{}", current_dir().unwrap().to_str().unwrap(), path.get_windows_path());
@@ -108,6 +146,89 @@ pub mod rustssh {
         /// * `path` - System path to track
         /// TODO add error handling
         pub fn start_tracking(&mut self, path: &mut Remit::SystemPath) -> Result<(), IOError> {
+            return self.track(path, None);
+        }
+
+        /// Track a directory once a daemon job writing into it has finished, so the files it downloads aren't uploaded back. Windows only
+        /// reports changes made after the first read of the directory, so nothing written by the job is seen. Nothing is tracked if the
+        /// job fails or tracking is stopped before it finishes
+        /// # Arguments
+        /// * `path` - System path to track
+        /// * `job` - Id of the daemon job to wait for
+        pub fn start_tracking_after_job(&mut self, path: &mut Remit::SystemPath, job: u64) -> Result<(), IOError> {
+            return self.track(path, Some(job));
+        }
+
+        /// Skip the changes under a remote directory until a daemon job writing into it has finished, so the files it downloads into an
+        /// already tracked directory aren't uploaded back. Changes are skipped for a few more seconds after the job, since they are read
+        /// from Windows about once a second
+        /// # Arguments
+        /// * `remote_dir` - Absolute remote path of the directory e.g. /home/user/project
+        /// * `job` - Id of the daemon job to wait for
+        pub fn skip_during_job(&mut self, remote_dir: String, job: u64) {
+            let skipped_dir = remote_dir.trim_start_matches('/').trim_end_matches('/').replace("/", "\\");
+            self.skipped.lock().unwrap().push(skipped_dir.clone());
+            let thread_flag = self.thread_control.clone();
+            let thread_rclone = self.rclone_instance.clone();
+            let thread_skipped = self.skipped.clone();
+            spawn(move || {
+                DirectoryTracker::wait_for_job(&thread_rclone, job, &thread_flag);
+                std::thread::sleep(std::time::Duration::from_millis(3000));
+                let mut skipped = thread_skipped.lock().unwrap();
+                if let Some(index) = skipped.iter().position(|dir| *dir == skipped_dir) {
+                    skipped.remove(index);
+                }
+            });
+        }
+
+        /// Check if a change is under a directory whose changes are being skipped
+        /// # Arguments
+        /// * `skipped` - Skipped directories, see [`DirectoryTracker::skip_during_job`]
+        /// * `remote_path` - Relative windows path of the changed file on the remote
+        #[cfg(windows)]
+        fn is_skipped(skipped: &Arc::<Mutex::<Vec<String>>>, remote_path: &String) -> bool {
+            return skipped.lock().unwrap().iter().any(|dir| dir.len() == 0 || remote_path == dir || remote_path.starts_with(&format!("{}\\", dir)));
+        }
+
+        /// Wait for a daemon job to finish. Returns true if it succeeded and tracking wasn't stopped meanwhile
+        /// # Arguments
+        /// * `rclone_instance` - Shared rclone manager owning the daemon
+        /// * `job` - Id of the daemon job
+        /// * `thread_flag` - Status of the tracking thread
+        fn wait_for_job(rclone_instance: &Arc::<Mutex::<Remit::RCloneManager>>, job: u64, thread_flag: &Arc<Mutex::<ThreadStatus>>) -> bool {
+            while *thread_flag.lock().unwrap() == ThreadStatus::Resume {
+                // only lock the manager to get the client, the daemon can be slow to answer
+                let status = rclone_instance.lock().unwrap().daemon_client().and_then(|client| client.job_status(job));
+                match status {
+                    Ok(status) if status.finished => return status.success && *thread_flag.lock().unwrap() == ThreadStatus::Resume,
+                    Ok(_) => std::thread::sleep(std::time::Duration::from_millis(500)),
+                    Err(e) => {
+                        println!("could not follow download job {}: {}", job, e);
+                        return false;
+                    }
+                }
+            }
+            return false;
+        }
+
+        /// Changes can't be tracked without the Win32 API. Only logs that the directory isn't tracked
+        /// # Arguments
+        /// * `path` - System path that would be tracked
+        /// * `_after_job` - Daemon job that would be waited for
+        #[cfg(not(windows))]
+        fn track(&mut self, path: &mut Remit::SystemPath, _after_job: Option<u64>) -> Result<(), IOError> {
+            *self.thread_control.lock().unwrap() = ThreadStatus::Kill;
+            (*self.path.lock().unwrap()) = path.clone();
+            println!("file changes are only tracked on MS Windows, not tracking {}", path.get_windows_path());
+            return Ok(());
+        }
+
+        /// Open the directory and spawn the tracking thread, optionally waiting for a daemon job before reading any changes
+        /// # Arguments
+        /// * `path` - System path to track
+        /// * `after_job` - Daemon job to wait for
+        #[cfg(windows)]
+        fn track(&mut self, path: &mut Remit::SystemPath, after_job: Option<u64>) -> Result<(), IOError> {
             self.set_dir_handle(path)?;
 
             // set variables for multithreading
@@ -118,9 +239,18 @@ pub mod rustssh {
             let shared_dir_handle = self.dir_handle.clone();
             let shared_consumer = self.consumer.clone();
             let thread_path = self.path.clone();
+            let thread_remote_root = self.remote_root.clone();
+            let thread_rclone = self.rclone_instance.clone();
+            let thread_skipped = self.skipped.clone();
 
             // start thread to monitor any changes. Changes are pushed into the consumers queue in fileeventconsumer
             spawn(move || -> Result<(), IOError>{
+                if let Some(job) = after_job {
+                    if !DirectoryTracker::wait_for_job(&thread_rclone, job, &thread_flag) {
+                        return Ok(());
+                    }
+                }
+
                 // start consumer
                 {
                     shared_consumer.lock().unwrap().start();
@@ -176,8 +306,12 @@ pub mod rustssh {
                                 // only push valid actions not 0
                                 if (*info).Action != 0 {
                                     let filename = DirectoryTracker::filename_from_notify_obj(&*info);
-                                    shared_consumer.lock().unwrap().add_event(Remit::FileEvent::new((*info).Action, format!("{}\\{}", (*thread_path.lock().unwrap()).get_windows_path(), filename),
-                                                                                                        filename.clone()));
+                                    let remote_path = format!("{}{}", *thread_remote_root.lock().unwrap(), filename);
+                                    if !DirectoryTracker::is_skipped(&thread_skipped, &remote_path) {
+                                        shared_consumer.lock().unwrap().add_event(Remit::FileEvent::new((*info).Action, format!("{}\\{}", (*thread_path.lock().unwrap()).get_windows_path(), filename),
+                                                                                                            remote_path,
+                                                                                                            (*thread_path.lock().unwrap()).get_windows_path()));
+                                    }
                                 }
                                 if (*info).NextEntryOffset == 0 {
                                     break;
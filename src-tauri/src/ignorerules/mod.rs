@@ -0,0 +1,428 @@
+//! gitignore style rules for files that should never be transferred, like editor swap files or build output. Each configuration
+//! has its own list of patterns and any directory can add more in a `.remitignore` file, which applies to that directory and
+//! everything below it.
+//!
+//! Patterns follow gitignore: `#` starts a comment, `!` re-includes a path excluded by an earlier pattern, a trailing `/` only matches
+//! directories and a pattern containing a `/` anywhere else is matched from the directory of the file it is in. Patterns without a
+//! `/` match a file or directory name at any depth. `*` and `?` don't match `/`, `**` matches any number of directories and `[a-z]`
+//! matches a class of characters. Later patterns win over earlier ones, and patterns of deeper `.remitignore` files win over the
+//! configuration's patterns. Everything inside an ignored directory is ignored.
+//!
+//! rclone applies the first filter rule that matches instead of the last, so [`IgnoreRules::filter_rules`] emits the translated
+//! rules in reverse order.
+
+pub mod rustssh {
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+/// Name of the per directory ignore file
+pub static IGNORE_FILE: &str = ".remitignore";
+
+/// A single ignore pattern
+#[derive(Clone, Debug)]
+struct IgnoreRule {
+    /// Glob without the leading / and trailing / of the original line
+    pattern: String,
+    /// Path excluded by an earlier rule is included again
+    negated: bool,
+    /// Only matches directories
+    dir_only: bool,
+    /// Matched against the path relative to base instead of against the name
+    anchored: bool,
+    /// Directory the rule applies to, relative to the root and separated by /. Empty for the root
+    base: String
+}
+
+/// Ignore rules of a directory tree
+#[derive(Clone, Debug)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>
+}
+
+#[allow(dead_code)]
+impl IgnoreRules {
+    /// Create an empty set of rules that ignores nothing
+    pub fn new() -> IgnoreRules {
+        return IgnoreRules{rules: Vec::new()};
+    }
+
+    /// Load the rules that apply to a directory: the configuration's patterns followed by the `.remitignore` files of the root and
+    /// every directory down to dir
+    /// # Arguments
+    /// * `root` - Local directory the paths are relative to
+    /// * `patterns` - Patterns of the configuration. They apply to the whole tree
+    /// * `dir` - Directory relative to root, separated by /. Empty for the root
+    pub fn load(root: &Path, patterns: &Vec<String>, dir: &str) -> IgnoreRules {
+        let mut rules = IgnoreRules::new();
+        rules.add(&patterns.join("\n"), "");
+        let mut base = String::new();
+        rules.add_file(root, &base);
+        for component in dir.split('/').filter(|c| c.len() > 0) {
+            base = IgnoreRules::join(&base, component);
+            rules.add_file(root, &base);
+        }
+        return rules;
+    }
+
+    /// Add the `.remitignore` files of every directory below dir. Directories that are already ignored aren't searched
+    /// # Arguments
+    /// * `root` - Local directory the paths are relative to
+    /// * `dir` - Directory relative to root to search, separated by /
+    pub fn load_below(&mut self, root: &Path, dir: &str) {
+        let entries = match read_dir(root.join(dir)) {
+            Ok(entries) => entries,
+            Err(_) => return
+        };
+        let mut dirs: Vec<String> = entries.filter_map(|e| e.ok()).filter(|e| e.file_type().map_or(false, |t| t.is_dir()))
+                                           .map(|e| IgnoreRules::join(dir, &e.file_name().to_string_lossy())).collect();
+        dirs.sort();
+        for sub_dir in dirs {
+            if self.is_ignored(&sub_dir, true) {
+                continue;
+            }
+            self.add_file(root, &sub_dir);
+            self.load_below(root, &sub_dir);
+        }
+    }
+
+    /// Add the rules of the `.remitignore` file in a directory if it has one
+    /// # Arguments
+    /// * `root` - Local directory the paths are relative to
+    /// * `base` - Directory containing the file, relative to root
+    fn add_file(&mut self, root: &Path, base: &str) {
+        let mut file = PathBuf::from(root);
+        file.push(base);
+        file.push(IGNORE_FILE);
+        if let Ok(contents) = read_to_string(file) {
+            self.add(&contents, base);
+        }
+    }
+
+    /// Parse gitignore style lines and add them after the existing rules
+    /// # Arguments
+    /// * `text` - Lines of patterns
+    /// * `base` - Directory the patterns apply to, relative to the root and separated by /. Empty for the root
+    pub fn add(&mut self, text: &str, base: &str) {
+        for line in text.lines() {
+            let line = line.trim_end();
+            if line.len() == 0 || line.starts_with('#') {
+                continue;
+            }
+            let negated = line.starts_with('!');
+            let mut pattern = if negated { &line[1..] } else { line };
+            // a leading backslash escapes a literal # or !
+            if pattern.starts_with("\\#") || pattern.starts_with("\\!") {
+                pattern = &pattern[1..];
+            }
+            let dir_only = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            let pattern = pattern.trim_start_matches('/');
+            if pattern.len() == 0 {
+                continue;
+            }
+            self.rules.push(IgnoreRule{pattern: pattern.to_string(), negated: negated, dir_only: dir_only, anchored: anchored,
+                                       base: base.trim_matches('/').to_string()});
+        }
+    }
+
+    /// Check if a path is ignored, either itself or because one of its parent directories is
+    /// # Arguments
+    /// * `path` - Path relative to the root, separated by /
+    /// * `is_dir` - True if the path is a directory
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let components: Vec<&str> = path.split('/').filter(|c| c.len() > 0).collect();
+        for i in 1..=components.len() {
+            let partial = components[0..i].join("/");
+            let partial_is_dir = i < components.len() || is_dir;
+            let mut ignored = false;
+            for rule in &self.rules {
+                if rule.dir_only && !partial_is_dir {
+                    continue;
+                }
+                let relative = if rule.base.len() == 0 {
+                    partial.as_str()
+                } else if partial.starts_with(&rule.base) && partial[rule.base.len()..].starts_with('/') {
+                    &partial[rule.base.len() + 1..]
+                } else {
+                    continue;
+                };
+                let subject = if rule.anchored { relative } else { components[i - 1] };
+                if glob_match(&rule.pattern.chars().collect::<Vec<char>>(), &subject.chars().collect::<Vec<char>>()) {
+                    ignored = !rule.negated;
+                }
+            }
+            if ignored {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /// Translate the rules into rclone filter rules e.g. `- *.swp`, for a transfer of one directory. Patterns anchored above that
+    /// directory that can't be expressed relative to it are left out. So are negations inside a directory ignored by an earlier
+    /// rule, which would otherwise come first for rclone and include files that [`IgnoreRules::is_ignored`] ignores
+    /// # Arguments
+    /// * `dir` - Directory being transferred, relative to the root and separated by /. Empty for the root
+    pub fn filter_rules(&self, dir: &str) -> Vec<String> {
+        let dir = dir.trim_matches('/');
+        let mut filters: Vec<String> = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.negated && self.negates_inside_ignored_dir(index) {
+                continue;
+            }
+            let pattern = rule.pattern.replace("[!", "[^");
+            let mut patterns: Vec<String> = Vec::new();
+            if let Some(prefix) = IgnoreRules::strip_dir(&rule.base, dir) {
+                // the rule's directory is inside the transferred directory
+                if rule.anchored {
+                    patterns.push(format!("/{}", IgnoreRules::join(prefix, &pattern)));
+                } else if prefix.len() == 0 {
+                    patterns.push(pattern);
+                } else {
+                    patterns.push(format!("/{}/{}", prefix, pattern));
+                    patterns.push(format!("/{}/**/{}", prefix, pattern));
+                }
+            } else if let Some(sub_dir) = IgnoreRules::strip_dir(dir, &rule.base) {
+                // the transferred directory is inside the rule's directory
+                if !rule.anchored {
+                    patterns.push(pattern);
+                } else if let Some(rest) = IgnoreRules::strip_dir(&pattern, sub_dir) {
+                    if rest.len() > 0 {
+                        patterns.push(format!("/{}", rest));
+                    }
+                }
+            }
+            let sign = if rule.negated { "+" } else { "-" };
+            for pattern in patterns {
+                if !rule.dir_only {
+                    filters.push(format!("{} {}", sign, pattern));
+                }
+                filters.push(format!("{} {}/**", sign, pattern));
+            }
+        }
+        filters.reverse();
+        return filters;
+    }
+
+    /// Check if a negated rule only matches paths inside a directory ignored by the rules before it. Those paths stay ignored
+    /// # Arguments
+    /// * `index` - Index of the negated rule
+    fn negates_inside_ignored_dir(&self, index: usize) -> bool {
+        let rule = &self.rules[index];
+        let mut dir = rule.base.clone();
+        if rule.anchored {
+            // the directories of the pattern up to the first one containing a glob
+            let components: Vec<&str> = rule.pattern.split('/').collect();
+            for component in &components[..components.len() - 1] {
+                if component.contains(|c: char| "*?[\\".contains(c)) {
+                    break;
+                }
+                dir = IgnoreRules::join(&dir, component);
+            }
+        }
+        if dir.len() == 0 {
+            return false;
+        }
+        let earlier = IgnoreRules{rules: self.rules[..index].to_vec()};
+        return earlier.is_ignored(&dir, true);
+    }
+
+    /// Get the part of a / separated path below a directory, or None if the path isn't the directory or inside it
+    /// # Arguments
+    /// * `path` - Path to strip
+    /// * `dir` - Directory to remove from the front. Empty for the root
+    fn strip_dir<'a>(path: &'a str, dir: &str) -> Option<&'a str> {
+        if dir.len() == 0 {
+            return Some(path);
+        }
+        if path == dir {
+            return Some("");
+        }
+        if path.starts_with(dir) && path[dir.len()..].starts_with('/') {
+            return Some(&path[dir.len() + 1..]);
+        }
+        return None;
+    }
+
+    /// Join two / separated paths, either of which may be empty
+    fn join(a: &str, b: &str) -> String {
+        if a.len() == 0 {
+            return b.to_string();
+        }
+        if b.len() == 0 {
+            return a.to_string();
+        }
+        return format!("{}/{}", a, b);
+    }
+}
+
+/// Match a path against a glob. `*` and `?` don't match /, `**` matches anything and `**/` also matches no directory at all
+/// # Arguments
+/// * `pattern` - Glob characters
+/// * `text` - Path characters
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    if pattern.len() == 0 {
+        return text.len() == 0;
+    }
+    match pattern[0] {
+        '*' if pattern.len() > 1 && pattern[1] == '*' => {
+            if pattern.len() > 2 && pattern[2] == '/' && glob_match(&pattern[3..], text) {
+                return true;
+            }
+            return (0..=text.len()).any(|i| glob_match(&pattern[2..], &text[i..]));
+        },
+        '*' => {
+            for i in 0..=text.len() {
+                if glob_match(&pattern[1..], &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    return false;
+                }
+            }
+            return false;
+        },
+        '?' => return text.len() > 0 && text[0] != '/' && glob_match(&pattern[1..], &text[1..]),
+        '[' => {
+            let end = match pattern.iter().skip(2).position(|c| *c == ']') {
+                Some(end) => end + 2,
+                // no closing bracket, match it literally
+                None => return text.len() > 0 && text[0] == '[' && glob_match(&pattern[1..], &text[1..])
+            };
+            if text.len() == 0 || text[0] == '/' {
+                return false;
+            }
+            let negated = pattern[1] == '!' || pattern[1] == '^';
+            let class = &pattern[if negated { 2 } else { 1 }..end];
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    matched |= class[i] <= text[0] && text[0] <= class[i + 2];
+                    i += 3;
+                } else {
+                    matched |= class[i] == text[0];
+                    i += 1;
+                }
+            }
+            return matched != negated && glob_match(&pattern[end + 1..], &text[1..]);
+        },
+        '\\' if pattern.len() > 1 => return text.len() > 0 && text[0] == pattern[1] && glob_match(&pattern[2..], &text[1..]),
+        c => return text.len() > 0 && text[0] == c && glob_match(&pattern[1..], &text[1..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Match a glob against a path
+    fn matches(pattern: &str, text: &str) -> bool {
+        return glob_match(&pattern.chars().collect::<Vec<char>>(), &text.chars().collect::<Vec<char>>());
+    }
+
+    /// Build rules for the root from lines of patterns
+    fn rules(text: &str) -> IgnoreRules {
+        let mut rules = IgnoreRules::new();
+        rules.add(text, "");
+        return rules;
+    }
+
+    #[test]
+    fn glob_match_wildcards_stop_at_slashes() {
+        assert!(matches("*.swp", ".main.rs.swp"));
+        assert!(!matches("*.swp", "src/main.swp"));
+        assert!(matches("?.txt", "a.txt"));
+        assert!(!matches("?.txt", "ab.txt"));
+        assert!(!matches("a?b", "a/b"));
+    }
+
+    #[test]
+    fn glob_match_double_star_matches_directories() {
+        assert!(matches("**/build", "build"));
+        assert!(matches("**/build", "a/b/build"));
+        assert!(matches("src/**", "src/a/b.rs"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(!matches("a/**/b", "a/x/c"));
+    }
+
+    #[test]
+    fn glob_match_classes_and_escapes() {
+        assert!(matches("file[0-9].txt", "file7.txt"));
+        assert!(!matches("file[0-9].txt", "filex.txt"));
+        assert!(matches("file[!0-9].txt", "filex.txt"));
+        assert!(!matches("file[^0-9].txt", "file1.txt"));
+        assert!(matches("\\*.txt", "*.txt"));
+        assert!(!matches("\\*.txt", "a.txt"));
+        assert!(matches("[abc", "[abc"));
+    }
+
+    #[test]
+    fn is_ignored_matches_names_at_any_depth() {
+        let rules = rules("# editor files\n*.swp\nnode_modules/\n");
+        assert!(rules.is_ignored("a.swp", false));
+        assert!(rules.is_ignored("src/deep/a.swp", false));
+        assert!(rules.is_ignored("web/node_modules", true));
+        assert!(rules.is_ignored("web/node_modules/pkg/index.js", false));
+        assert!(!rules.is_ignored("node_modules", false));
+        assert!(!rules.is_ignored("src/main.rs", false));
+    }
+
+    #[test]
+    fn is_ignored_anchors_patterns_with_a_slash() {
+        let rules = rules("/target\ndocs/*.pdf\n");
+        assert!(rules.is_ignored("target", true));
+        assert!(!rules.is_ignored("sub/target", true));
+        assert!(rules.is_ignored("docs/manual.pdf", false));
+        assert!(!rules.is_ignored("other/docs/manual.pdf", false));
+    }
+
+    #[test]
+    fn is_ignored_lets_later_negations_win() {
+        let logs = rules("*.log\n!keep.log\n");
+        assert!(logs.is_ignored("debug.log", false));
+        assert!(!logs.is_ignored("keep.log", false));
+        // a file can't be included again once its directory is ignored
+        let build = rules("build/\n!build/keep.txt\n");
+        assert!(build.is_ignored("build/keep.txt", false));
+    }
+
+    #[test]
+    fn is_ignored_scopes_rules_to_their_directory() {
+        let mut rules = IgnoreRules::new();
+        rules.add("*.tmp", "sub");
+        assert!(rules.is_ignored("sub/a.tmp", false));
+        assert!(rules.is_ignored("sub/deep/a.tmp", false));
+        assert!(!rules.is_ignored("a.tmp", false));
+        assert!(!rules.is_ignored("subway/a.tmp", false));
+    }
+
+    #[test]
+    fn filter_rules_are_reversed_for_rclone() {
+        let rules = rules("*.log\n!keep.log\nbuild/\n");
+        assert_eq!(rules.filter_rules(""), vec!["- build/**", "+ keep.log/**", "+ keep.log", "- *.log/**", "- *.log"]);
+    }
+
+    #[test]
+    fn filter_rules_leave_out_negations_inside_ignored_directories() {
+        let build = rules("build/\n!build/keep.txt\n!/build/sub/\n");
+        assert!(build.is_ignored("build/keep.txt", false));
+        assert_eq!(build.filter_rules(""), vec!["- build/**"]);
+        // the same negation applies once the directory isn't ignored
+        let reincluded = rules("build/\n!build/\n!build/keep.txt\n");
+        assert!(!reincluded.is_ignored("build/keep.txt", false));
+        assert_eq!(reincluded.filter_rules(""), vec!["+ /build/keep.txt/**", "+ /build/keep.txt", "+ build/**", "- build/**"]);
+    }
+
+    #[test]
+    fn filter_rules_are_relative_to_the_transferred_directory() {
+        let mut rules = rules("/web/dist\n/top.txt\n");
+        rules.add("*.tmp", "web/src");
+        assert_eq!(rules.filter_rules("web"), vec!["- /src/**/*.tmp/**", "- /src/**/*.tmp", "- /src/*.tmp/**", "- /src/*.tmp",
+                                                   "- /dist/**", "- /dist"]);
+    }
+}
+
+}
\ No newline at end of file
@@ -0,0 +1,244 @@
+//! The journal records every mutating operation Remit performs on the remote server so that it can be undone and redone. Each entry holds
+//! enough information to invert the operation: a rename remembers both paths, a chmod remembers the previous mode and a delete remembers
+//! the id of the trash entry the file was moved to. An undone create remembers the trash entry too, so redoing it brings back the content.
+//! Deletes are moved into the trash even while it is disabled, those entries are only kept until the session ends.
+//! 
+//! A journal covers one session, from connecting to disconnecting. It is saved after every change under
+//! `configs/journal/<config name>/<session start>.journal` so the history of earlier sessions can still be viewed. Each line of the file
+//! is one tab separated entry:
+//! 
+//! ```
+//! done|undone	time	create	path	true|false	trash id or -
+//! done|undone	time	rename	old path	new path
+//! done|undone	time	delete	path	trash id or -
+//! done|undone	time	chmod	path	old mode	new mode
+//! ```
+
+pub mod rustssh {
+use std::fs::{create_dir_all, read_dir, read_to_string, write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::*;
+
+/// A mutating operation on the remote server. All paths are absolute remote paths
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// A file or directory was created. Once undone, the trash id of the removed file is kept
+    Create{path: String, is_dir: bool, trash_id: Option<String>},
+    /// A file or directory was renamed or moved
+    Rename{from: String, to: String},
+    /// A file or directory was deleted. If it was moved to the trash, the trash id is kept
+    Delete{path: String, trash_id: Option<String>},
+    /// The permissions of a file were changed. Modes are octal strings e.g. 644
+    Chmod{path: String, old_mode: String, new_mode: String}
+}
+
+impl Operation {
+    /// Get a short human readable description of the operation
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Create{path, ..}=> return format!("create {}", path),
+            Operation::Rename{from, to}=> return format!("rename {} to {}", from, to),
+            Operation::Delete{path, ..}=> return format!("delete {}", path),
+            Operation::Chmod{path, old_mode, new_mode}=> return format!("chmod {} from {} to {}", path, old_mode, new_mode)
+        }
+    }
+
+    /// Convert the operation into tab separated fields for the journal file
+    fn to_fields(&self) -> String {
+        match self {
+            Operation::Create{path, is_dir, trash_id}=> return format!("create\t{}\t{}\t{}", path, is_dir, trash_id.clone().unwrap_or("-".to_string())),
+            Operation::Rename{from, to}=> return format!("rename\t{}\t{}", from, to),
+            Operation::Delete{path, trash_id}=> return format!("delete\t{}\t{}", path, trash_id.clone().unwrap_or("-".to_string())),
+            Operation::Chmod{path, old_mode, new_mode}=> return format!("chmod\t{}\t{}\t{}", path, old_mode, new_mode)
+        }
+    }
+
+    /// Parse the fields written by [`Operation::to_fields`]
+    /// # Arguments
+    /// * `fields` - Fields after the state and time
+    fn from_fields(fields: &[&str]) -> Option<Operation> {
+        match fields {
+            ["create", path, is_dir]=> return Some(Operation::Create{path: path.to_string(), is_dir: *is_dir == "true", trash_id: None}),
+            ["create", path, is_dir, trash_id]=> {
+                let id = if *trash_id == "-" { None } else { Some(trash_id.to_string()) };
+                return Some(Operation::Create{path: path.to_string(), is_dir: *is_dir == "true", trash_id: id});
+            },
+            ["rename", from, to]=> return Some(Operation::Rename{from: from.to_string(), to: to.to_string()}),
+            ["delete", path, trash_id]=> {
+                let id = if *trash_id == "-" { None } else { Some(trash_id.to_string()) };
+                return Some(Operation::Delete{path: path.to_string(), trash_id: id});
+            },
+            ["chmod", path, old_mode, new_mode]=> return Some(Operation::Chmod{path: path.to_string(), old_mode: old_mode.to_string(),
+                                                                               new_mode: new_mode.to_string()}),
+            _=> return None
+        }
+    }
+}
+
+/// A recorded operation
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    /// When the operation was performed in seconds since the unix epoch
+    pub time: u64,
+    pub operation: Operation,
+    /// True if the operation has been undone
+    pub undone: bool
+}
+
+/// Undo and redo history of the current session
+pub struct Journal {
+    /// Operations that can be undone, most recent last
+    done: Vec<JournalEntry>,
+    /// Operations that have been undone and can be redone, most recently undone last
+    undone: Vec<JournalEntry>,
+    /// Directory containing the journal files of the configuration
+    dir: Remit::SystemPath,
+    /// Id of the session, the time it started
+    session: String
+}
+
+#[allow(dead_code)]
+impl Journal {
+    /// Create an empty journal that is not saved to disc. Use [`Journal::start_session`] to begin recording
+    pub fn new() -> Journal {
+        return Journal{done: Vec::new(), undone: Vec::new(), dir: Remit::SystemPath::new(), session: String::new()};
+    }
+
+    /// Get the current time in seconds since the unix epoch
+    fn now() -> u64 {
+        return SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    }
+
+    /// Clear the history and start a new session for a configuration
+    /// # Arguments
+    /// * `config_dir` - Directory containing the Remit configurations
+    /// * `config` - Name of the configuration
+    pub fn start_session(&mut self, config_dir: &Remit::SystemPath, config: &String) {
+        let mut dir = config_dir.clone();
+        dir.pushd("journal".to_string());
+        dir.pushd(config.clone());
+        self.dir = dir;
+        self.session = Journal::now().to_string();
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    /// Record a newly performed operation. Anything that was undone can no longer be redone
+    /// # Arguments
+    /// * `operation` - The operation that was performed
+    pub fn record(&mut self, operation: Operation) -> Result<(), IOError> {
+        self.done.push(JournalEntry{time: Journal::now(), operation: operation, undone: false});
+        self.undone.clear();
+        return self.save();
+    }
+
+    /// Get the most recent operation that can be undone without removing it
+    pub fn peek_undo(&self) -> Option<JournalEntry> {
+        return self.done.last().cloned();
+    }
+
+    /// Get the most recently undone operation without removing it
+    pub fn peek_redo(&self) -> Option<JournalEntry> {
+        return self.undone.last().cloned();
+    }
+
+    /// Mark the most recent operation as undone. The operation stored can be replaced, for example with a new trash id
+    /// # Arguments
+    /// * `operation` - The operation as it should be redone
+    pub fn mark_undone(&mut self, operation: Operation) -> Result<(), IOError> {
+        if let Some(mut entry) = self.done.pop() {
+            entry.operation = operation;
+            entry.undone = true;
+            self.undone.push(entry);
+        }
+        return self.save();
+    }
+
+    /// Drop the most recent operation without undoing it, so an operation that can't be undone doesn't block the ones before it
+    pub fn discard_undo(&mut self) -> Result<(), IOError> {
+        self.done.pop();
+        return self.save();
+    }
+
+    /// Mark the most recently undone operation as done again
+    /// # Arguments
+    /// * `operation` - The operation as it should be undone
+    pub fn mark_redone(&mut self, operation: Operation) -> Result<(), IOError> {
+        if let Some(mut entry) = self.undone.pop() {
+            entry.operation = operation;
+            entry.undone = false;
+            entry.time = Journal::now();
+            self.done.push(entry);
+        }
+        return self.save();
+    }
+
+    /// Get every entry of the current session in the order they were performed. Undone entries come last
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        let mut entries = self.done.clone();
+        entries.extend(self.undone.iter().rev().cloned());
+        return entries;
+    }
+
+    /// Write the current session to disc. Does nothing before a session is started
+    fn save(&self) -> Result<(), IOError> {
+        if self.session.len() == 0 {
+            return Ok(());
+        }
+        create_dir_all(self.dir.get_windows_path_local())?;
+        let mut contents = String::new();
+        for entry in self.entries() {
+            let state = if entry.undone { "undone" } else { "done" };
+            contents += &format!("{}\t{}\t{}\n", state, entry.time, entry.operation.to_fields());
+        }
+        let mut file = self.dir.clone();
+        file.pushd(format!("{}.journal", self.session));
+        return write(file.get_windows_path_local(), contents);
+    }
+
+    /// List the ids of every saved session of the current configuration, oldest first
+    pub fn sessions(&self) -> Result<Vec<String>, IOError> {
+        let mut sessions: Vec<String> = Vec::new();
+        if self.session.len() == 0 {
+            return Ok(sessions);
+        }
+        let paths = match read_dir(self.dir.get_windows_path_local()) {
+            Ok(paths) => paths,
+            // nothing has been recorded for this configuration yet
+            Err(e) if e.kind() == IOErrorKind::NotFound => return Ok(vec![self.session.clone()]),
+            Err(e) => return Err(e)
+        };
+        for path in paths {
+            let name = path?.file_name().to_string_lossy().to_string();
+            if let Some(session) = name.strip_suffix(".journal") {
+                sessions.push(session.to_string());
+            }
+        }
+        sessions.sort_by_key(|s| s.parse::<u64>().unwrap_or(0));
+        return Ok(sessions);
+    }
+
+    /// Load the entries of a saved session
+    /// # Arguments
+    /// * `session` - Id of the session. See [`Journal::sessions`]
+    pub fn load_session(&self, session: &String) -> Result<Vec<JournalEntry>, IOError> {
+        if *session == self.session {
+            return Ok(self.entries());
+        }
+        let mut file = self.dir.clone();
+        file.pushd(format!("{}.journal", session));
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for line in read_to_string(file.get_windows_path_local())?.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 3 {
+                continue;
+            }
+            if let Some(operation) = Operation::from_fields(&fields[2..]) {
+                entries.push(JournalEntry{time: fields[1].parse::<u64>().unwrap_or(0), operation: operation, undone: fields[0] == "undone"});
+            }
+        }
+        return Ok(entries);
+    }
+}
+
+}
@@ -1,10 +1,20 @@
+mod bisyncmanager;
+mod checksum;
 mod configmanager;
 mod fileeventconsumer;
+mod filepreview;
 mod filetracker;
+mod ignorerules;
+mod journal;
+mod listingcache;
 mod manager;
+mod rcdaemon;
 mod sessionmanager;
 mod syncmanager;
 mod systempaths;
+mod textdiff;
+mod trashmanager;
+mod transferqueue;
 
 pub type RemitManager = manager::rustssh::Manager;
 pub type IOError = std::io::Error;
@@ -14,13 +24,54 @@ pub type RemitConfig = crate::configmanager::rustssh::RemitConfig;
 pub mod Remit{
     pub type SystemPath = crate::systempaths::rustssh::SystemPath;
     pub type RCloneManager = crate::syncmanager::rustssh::RCloneManager;
+    pub type Backend = crate::syncmanager::rustssh::Backend;
+    pub type PlannedAction = crate::syncmanager::rustssh::PlannedAction;
+    pub type PlannedChange = crate::syncmanager::rustssh::PlannedChange;
+    pub type DirectoryFilter = crate::syncmanager::rustssh::DirectoryFilter;
+    pub type TransferLimits = crate::syncmanager::rustssh::TransferLimits;
+    pub type RCloneVersion = crate::syncmanager::rustssh::RCloneVersion;
+    pub type RCloneFeature = crate::syncmanager::rustssh::RCloneFeature;
+    pub type RCloneDaemon = crate::rcdaemon::rustssh::RCloneDaemon;
+    pub type RcClient = crate::rcdaemon::rustssh::RcClient;
+    pub type JobStatus = crate::rcdaemon::rustssh::JobStatus;
+    pub type TransferStats = crate::rcdaemon::rustssh::TransferStats;
+    pub type TransferProgress = crate::rcdaemon::rustssh::TransferProgress;
+    pub type TransferResult = crate::rcdaemon::rustssh::TransferResult;
+    pub type TransferSource = crate::rcdaemon::rustssh::TransferSource;
+    pub type TransferEvent = crate::rcdaemon::rustssh::TransferEvent;
+    pub type TransferListener = crate::rcdaemon::rustssh::TransferListener;
+    pub type BisyncManager = crate::bisyncmanager::rustssh::BisyncManager;
+    pub type SyncPair = crate::bisyncmanager::rustssh::SyncPair;
+    pub type BisyncResult = crate::bisyncmanager::rustssh::BisyncResult;
+    pub type BisyncListener = crate::bisyncmanager::rustssh::BisyncListener;
+    pub type TransferQueue = crate::transferqueue::rustssh::TransferQueue;
+    pub type Transfer = crate::transferqueue::rustssh::Transfer;
+    pub type TransferKind = crate::transferqueue::rustssh::TransferKind;
+    pub type TransferState = crate::transferqueue::rustssh::TransferState;
     pub type Directory = crate::sessionmanager::rustssh::Directory;
+    pub type RemitFile = crate::sessionmanager::rustssh::RemitFile;
+    pub type FileType = crate::sessionmanager::rustssh::FileType;
+    pub type ListingOptions = crate::sessionmanager::rustssh::ListingOptions;
+    pub type SortKey = crate::sessionmanager::rustssh::SortKey;
     pub type ConfigManager = crate::configmanager::rustssh::ConfigManager;
     pub type Config = crate::configmanager::rustssh::RemitConfig;
+    pub type RecentHistory = crate::configmanager::rustssh::RecentHistory;
+    pub type RecentPath = crate::configmanager::rustssh::RecentPath;
+    pub type IgnoreRules = crate::ignorerules::rustssh::IgnoreRules;
     pub type DirectoryTracker = crate::filetracker::rustssh::DirectoryTracker;
     pub type SessionManager = crate::sessionmanager::rustssh::SessionManager;
     pub type FileEventConsumer = crate::fileeventconsumer::rustssh::FileEventConsumer;
     pub type FileEvent = crate::fileeventconsumer::rustssh::FileEvent;
+    pub type ListingCache = crate::listingcache::rustssh::ListingCache;
+    pub type FilePreview = crate::filepreview::rustssh::FilePreview;
+    pub type PreviewKind = crate::filepreview::rustssh::PreviewKind;
+    pub type SyncStatus = crate::checksum::rustssh::SyncStatus;
+    pub type FileDiff = crate::textdiff::rustssh::FileDiff;
+    pub type TrashManager = crate::trashmanager::rustssh::TrashManager;
+    pub type TrashItem = crate::trashmanager::rustssh::TrashItem;
+    pub type Journal = crate::journal::rustssh::Journal;
+    pub type JournalEntry = crate::journal::rustssh::JournalEntry;
+    pub type Operation = crate::journal::rustssh::Operation;
 }
 
 #[derive(Eq, PartialEq)]
@@ -0,0 +1,91 @@
+//! The listing cache keeps the results of previous directory listings for the current session. Every call to
+//! [`crate::sessionmanager::rustssh::SessionManager::get_directory`] runs a `stat` over ssh, which is noticeable on high-latency
+//! links when navigating back and forth between the same folders. Listings are stored by their remote path and expire after a
+//! configurable time to live.
+//!
+//! The cache does not watch the remote server. Remit's own create, rename and delete operations invalidate the affected paths
+//! through the [`ListingCache::invalidate`] and [`ListingCache::invalidate_tree`] methods. Changes made by other programs will only
+//! show once the entry expires or a forced refresh is requested.
+
+pub mod rustssh {
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use crate::*;
+
+/// A cached directory listing along with the time it was stored
+#[derive(Clone)]
+struct CacheEntry {
+    /// When the listing was retrieved from the server
+    created: Instant,
+    /// Contents of the directory by file name
+    files: BTreeMap<String, Remit::RemitFile>
+}
+
+/// Per session cache of directory listings stored by remote path
+pub struct ListingCache {
+    /// All cached listings stored by their remote path. e.g. /home/user
+    entries: HashMap<String, CacheEntry>,
+    /// How long a listing is considered valid
+    ttl: Duration
+}
+
+#[allow(dead_code)]
+impl ListingCache {
+    /// Create an empty cache
+    /// # Arguments
+    /// * `ttl` - How long a listing stays valid. If None, assume 30 seconds
+    pub fn new(ttl: Option<Duration>) -> ListingCache {
+        return ListingCache{entries: HashMap::new(), ttl: ttl.unwrap_or(Duration::from_secs(30))};
+    }
+
+    /// Change how long listings stay valid. Existing entries are checked against the new value
+    /// # Arguments
+    /// * `ttl` - New time to live
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    /// Get the listing for a remote path if it exists and has not expired. Expired entries are removed
+    /// # Arguments
+    /// * `path` - Remote path of the directory
+    pub fn get(&mut self, path: &String) -> Option<BTreeMap<String, Remit::RemitFile>> {
+        let expired = match self.entries.get(path) {
+            Some(entry) => entry.created.elapsed() > self.ttl,
+            None => return None
+        };
+        if expired {
+            self.entries.remove(path);
+            return None;
+        }
+        return self.entries.get(path).map(|entry| entry.files.clone());
+    }
+
+    /// Store the listing of a directory. Any existing listing for the path is replaced
+    /// # Arguments
+    /// * `dir` - Directory containing the path and its loaded files
+    pub fn insert(&mut self, dir: &Remit::Directory) {
+        self.entries.insert(dir.path.get_path(), CacheEntry{created: Instant::now(), files: dir.files.clone()});
+    }
+
+    /// Remove the listing for a single remote path
+    /// # Arguments
+    /// * `path` - Remote path of the directory
+    pub fn invalidate(&mut self, path: &String) {
+        self.entries.remove(path);
+    }
+
+    /// Remove the listing for a remote path and every listing below it. Used when a directory is renamed or deleted
+    /// # Arguments
+    /// * `path` - Remote path at the top of the tree to remove
+    pub fn invalidate_tree(&mut self, path: &String) {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        self.entries.retain(|key, _| key != path && !key.starts_with(&prefix));
+    }
+
+    /// Remove all cached listings. Should be called whenever the session changes
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+}
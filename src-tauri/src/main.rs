@@ -7,11 +7,13 @@
 //! with the tauri::command macro are available for the rendered web view to hook. The other parts of the file
 //! are set up and configuration
 
-use tauri::{plugin::{Plugin, Result as PluginResult}, Runtime, PageLoadPayload, Window, Invoke, AppHandle};
+use tauri::{plugin::{Plugin, Result as PluginResult}, Runtime, PageLoadPayload, Window, Invoke, AppHandle, RunEvent, Manager};
 use std::{sync::Mutex, sync::MutexGuard};
 use once_cell::sync::Lazy;
 use std::path::Path;
 use std::collections::HashMap;
+use std::sync::Arc;
+use serde::Serialize;
 
 use app::*;
 
@@ -25,6 +27,37 @@ static REMIT_API: Lazy<Mutex<RemitManager>> = Lazy::new(|| {
   return Mutex::new(manager);
 });
 
+/// Status of every paged listing in progress stored by listing id. Kept outside of REMIT_API so a listing
+/// can be cancelled while it holds the api lock
+static LISTINGS: Lazy<Mutex<HashMap<String, Arc<Mutex<ThreadStatus>>>>> = Lazy::new(|| {
+  return Mutex::new(HashMap::new());
+});
+
+/// Queue of uploads and downloads. Shared with REMIT_API but kept under its own lock so transfers can be managed while
+/// the api is busy
+static TRANSFERS: Lazy<Arc<Mutex<Remit::TransferQueue>>> = Lazy::new(|| {
+  return REMIT_API.lock().unwrap().transfer_queue();
+});
+
+/// Two way sync pairs of the current configuration. Kept under its own lock because a run can take a long time
+static SYNC_PAIRS: Lazy<Arc<Mutex<Remit::BisyncManager>>> = Lazy::new(|| {
+  return REMIT_API.lock().unwrap().bisync_manager();
+});
+
+/// A page of files emitted to the frontend during a paged listing
+#[derive(Clone, Serialize)]
+struct ListingPage {
+  /// Id passed in by the frontend when the listing was requested
+  listing_id: String,
+  /// Index of this page starting at 0
+  page: u32,
+  files: Vec<HashMap<String, String>>,
+  /// Set on the last page once the listing has finished
+  done: bool,
+  /// Total number of files in the directory. Only known once done is true
+  total: Option<u64>
+}
+
 struct Remit<R: Runtime> {
     invoke_handler: Box<dyn Fn(Invoke<R>) + Send + Sync>,
     // plugin state, configuration fields
@@ -51,6 +84,22 @@ struct Remit<R: Runtime> {
     }
   }
 
+  /// Helper method to use the transfer queue without locking the api. Works like [`run_api_command`]
+  /// # Arguments
+  /// * `output` - A mutable object of type T the callback can change
+  /// * `callback` - A function that takes in the output variable and the transfer queue
+  fn run_queue_command<T>(output: &mut T, callback: &dyn Fn(&mut T, &mut Remit::TransferQueue) -> Result<(), IOError>) -> Result<(), String> {
+    match TRANSFERS.lock() {
+      Ok(mut queue)=>{
+        match callback(output, &mut queue) {
+          Ok(_)=>return Ok(()),
+          Err(e)=> return Err(e.to_string())
+        }
+      }
+      Err(e)=> return Err(e.to_string())
+    }
+  }
+
   /// Download a file from the remote
   /// 
   /// # Arguments
@@ -66,6 +115,403 @@ struct Remit<R: Runtime> {
     return Ok(r);
   }
 
+  /// Start the rclone daemon so transfers can run in the background. Does nothing if it is already running
+  #[tauri::command]
+  async fn start_daemon() -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.start_daemon();
+    })?;
+    return Ok(());
+  }
+
+  /// Shut the rclone daemon down, stopping any running transfers
+  #[tauri::command]
+  async fn stop_daemon() -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      api.stop_daemon();
+      return Ok(());
+    })?;
+    return Ok(());
+  }
+
+  /// Start downloading a file from the current remote directory through the rclone daemon. Returns the job id
+  /// 
+  /// # Arguments
+  /// * `filename` - Name of file to download
+  #[tauri::command]
+  async fn download_async(filename: String) -> Result<u64, String> {
+    let mut id = 0u64;
+    run_api_command::<u64>(&mut id, &|id: &mut u64, api: &mut ApiRef| -> Result<(), IOError> {
+      *id = api.download_file_async(filename.clone())?;
+      return Ok(());
+    })?;
+    return Ok(id);
+  }
+
+  /// Convert the changes reported by a dry run to json. Each map has the action (copy, delete, update, mkdir or rmdir), the path
+  /// relative to the destination and the size in bytes if known
+  /// # Arguments
+  /// * `changes` - Changes a transfer would make
+  fn planned_changes_to_json(changes: Vec<Remit::PlannedChange>) -> Vec<HashMap<String, String>> {
+    let mut json = Vec::<HashMap::<String,String>>::new();
+    for change in changes {
+      let mut entry = HashMap::<String,String>::new();
+      entry.insert("action".to_string(), change.action.to_string());
+      entry.insert("path".to_string(), change.path);
+      if let Some(size) = change.size {
+        entry.insert("size".to_string(), size.to_string());
+      }
+      json.push(entry);
+    }
+    return json;
+  }
+
+  /// List the changes downloading a file would make to the local mirror without downloading it
+  /// 
+  /// # Arguments
+  /// * `filename` - Name of a file in the current remote directory
+  #[tauri::command]
+  async fn preview_download(filename: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut changes = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut changes, &|changes: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *changes = planned_changes_to_json(api.plan_download(filename.clone())?);
+      return Ok(());
+    })?;
+    return Ok(changes);
+  }
+
+  /// List the changes uploading a file from the local mirror would make on the server without uploading it
+  /// 
+  /// # Arguments
+  /// * `filename` - Name of a file in the current directory
+  #[tauri::command]
+  async fn preview_upload(filename: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut changes = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut changes, &|changes: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *changes = planned_changes_to_json(api.plan_upload(filename.clone())?);
+      return Ok(());
+    })?;
+    return Ok(changes);
+  }
+
+  /// List the changes [`download_async`] or [`queue_download`] would make to the local mirror without downloading the file
+  /// 
+  /// # Arguments
+  /// * `filename` - Name of a file in the current remote directory
+  #[tauri::command]
+  async fn preview_download_async(filename: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut changes = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut changes, &|changes: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *changes = planned_changes_to_json(api.plan_download_async(filename.clone())?);
+      return Ok(());
+    })?;
+    return Ok(changes);
+  }
+
+  /// List the changes [`upload_async`] or [`queue_upload`] would make on the server without uploading the file
+  /// 
+  /// # Arguments
+  /// * `filename` - Name of a file in the current directory
+  #[tauri::command]
+  async fn preview_upload_async(filename: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut changes = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut changes, &|changes: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *changes = planned_changes_to_json(api.plan_upload_async(filename.clone())?);
+      return Ok(());
+    })?;
+    return Ok(changes);
+  }
+
+  /// List the changes a directory download would make without downloading anything. Takes the same arguments as [`download_directory`]
+  #[tauri::command]
+  async fn preview_directory_download(name: Option<String>, localpath: Option<String>, maxdepth: Option<u32>, include: Option<Vec<String>>,
+                                      exclude: Option<Vec<String>>) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut changes = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut changes, &|changes: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      let filter = Remit::DirectoryFilter{max_depth: maxdepth, include: include.clone().unwrap_or_default(),
+                                          exclude: exclude.clone().unwrap_or_default()};
+      *changes = planned_changes_to_json(api.plan_directory_download(name.clone(), localpath.clone(), filter)?);
+      return Ok(());
+    })?;
+    return Ok(changes);
+  }
+
+  /// Download a whole remote directory in the background. Returns the job id, progress is sent as `remit://transfer-progress`
+  /// and `remit://transfer-finished` events with this id and a source of "job"
+  /// 
+  /// # Arguments
+  /// * `name` - Name of a directory in the current remote directory. If None the current directory is downloaded
+  /// * `localpath` - Folder to download into. If None the directory is copied into the local mirror
+  /// * `maxdepth` - Directory levels to download. 1 only downloads the files directly in the directory. If None download everything
+  /// * `include` - Glob patterns of files to download. If None or empty download every file not excluded
+  /// * `exclude` - Glob patterns of files to skip
+  #[tauri::command]
+  async fn download_directory(name: Option<String>, localpath: Option<String>, maxdepth: Option<u32>, include: Option<Vec<String>>,
+                              exclude: Option<Vec<String>>) -> Result<u64, String> {
+    let mut id = 0u64;
+    run_api_command::<u64>(&mut id, &|id: &mut u64, api: &mut ApiRef| -> Result<(), IOError> {
+      let filter = Remit::DirectoryFilter{max_depth: maxdepth, include: include.clone().unwrap_or_default(),
+                                          exclude: exclude.clone().unwrap_or_default()};
+      *id = api.download_directory(name.clone(), localpath.clone(), filter)?;
+      return Ok(());
+    })?;
+    return Ok(id);
+  }
+
+  /// Start uploading a file from the local mirror to the current remote directory through the rclone daemon. Returns the job id
+  /// 
+  /// # Arguments
+  /// * `filename` - Name of file to upload
+  #[tauri::command]
+  async fn upload_async(filename: String) -> Result<u64, String> {
+    let mut id = 0u64;
+    run_api_command::<u64>(&mut id, &|id: &mut u64, api: &mut ApiRef| -> Result<(), IOError> {
+      *id = api.upload_file_async(filename.clone())?;
+      return Ok(());
+    })?;
+    return Ok(id);
+  }
+
+  /// Get the status of a background transfer. The map has the job id, finished, success, error and duration in seconds
+  /// 
+  /// # Arguments
+  /// * `jobid` - Id returned when the transfer was started
+  #[tauri::command]
+  async fn job_status(jobid: u64) -> Result<HashMap<String, String>, String> {
+    let mut status = HashMap::<String, String>::new();
+    run_api_command::<HashMap::<String,String>>(&mut status, &|status: &mut HashMap<String, String>, api: &mut ApiRef| -> Result<(), IOError> {
+      let job = api.job_status(jobid)?;
+      status.insert("id".to_string(), job.id.to_string());
+      status.insert("finished".to_string(), job.finished.to_string());
+      status.insert("success".to_string(), job.success.to_string());
+      status.insert("error".to_string(), job.error);
+      status.insert("duration".to_string(), job.duration.to_string());
+      return Ok(());
+    })?;
+    return Ok(status);
+  }
+
+  /// Cancel a background transfer
+  /// 
+  /// # Arguments
+  /// * `jobid` - Id returned when the transfer was started
+  #[tauri::command]
+  async fn stop_job(jobid: u64) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.stop_job(jobid);
+    })?;
+    return Ok(());
+  }
+
+  /// Add a download of a file in the current remote directory to the transfer queue. Returns the transfer id
+  /// 
+  /// Progress and the result are sent as `remit://transfer-progress` and `remit://transfer-finished` events with this id and a
+  /// source of "queue"
+  /// # Arguments
+  /// * `filename` - Name of file to download
+  /// * `priority` - Higher priorities start first. If None assume 0
+  #[tauri::command]
+  async fn queue_download(filename: String, priority: Option<i32>) -> Result<u64, String> {
+    let mut id = 0u64;
+    run_api_command::<u64>(&mut id, &|id: &mut u64, api: &mut ApiRef| -> Result<(), IOError> {
+      *id = api.queue_download(filename.clone(), priority)?;
+      return Ok(());
+    })?;
+    return Ok(id);
+  }
+
+  /// Add an upload of a file from the local mirror to the current remote directory to the transfer queue. Returns the transfer id
+  /// 
+  /// # Arguments
+  /// * `filename` - Name of file to upload
+  /// * `priority` - Higher priorities start first. If None assume 0
+  #[tauri::command]
+  async fn queue_upload(filename: String, priority: Option<i32>) -> Result<u64, String> {
+    let mut id = 0u64;
+    run_api_command::<u64>(&mut id, &|id: &mut u64, api: &mut ApiRef| -> Result<(), IOError> {
+      *id = api.queue_upload(filename.clone(), priority)?;
+      return Ok(());
+    })?;
+    return Ok(id);
+  }
+
+  /// List every transfer in the queue in the order they were added. Each map has the id, kind (upload or download), filename,
+  /// source, destination, priority, state (queued, running, paused, done, failed or cancelled), error, bytes and totalbytes
+  #[tauri::command]
+  async fn list_transfers() -> Result<Vec<HashMap<String, String>>, String> {
+    let mut transfers = Vec::<HashMap::<String,String>>::new();
+    run_queue_command::<Vec::<HashMap::<String,String>>>(&mut transfers, &|transfers: &mut Vec<HashMap<String, String>>, queue: &mut Remit::TransferQueue| -> Result<(), IOError> {
+      for t in queue.list() {
+        let mut transfer = HashMap::<String,String>::new();
+        transfer.insert("id".to_string(), t.id.to_string());
+        transfer.insert("kind".to_string(), t.kind.to_string());
+        transfer.insert("filename".to_string(), t.filename);
+        transfer.insert("source".to_string(), t.source);
+        transfer.insert("destination".to_string(), t.destination);
+        transfer.insert("priority".to_string(), t.priority.to_string());
+        transfer.insert("state".to_string(), t.state.to_string());
+        transfer.insert("error".to_string(), t.error);
+        transfer.insert("bytes".to_string(), t.bytes.to_string());
+        transfer.insert("totalbytes".to_string(), t.total_bytes.to_string());
+        transfer.insert("retries".to_string(), t.retries.to_string());
+        transfers.push(transfer);
+      }
+      return Ok(());
+    })?;
+    return Ok(transfers);
+  }
+
+  /// Pause a queued or running transfer. A running transfer starts over when resumed
+  /// 
+  /// # Arguments
+  /// * `transferid` - Id returned when the transfer was queued
+  #[tauri::command]
+  async fn pause_transfer(transferid: u64) -> Result<(), String> {
+    return Remit::TransferQueue::pause(&TRANSFERS, transferid).map_err(|e| e.to_string());
+  }
+
+  /// Queue a paused transfer again
+  /// 
+  /// # Arguments
+  /// * `transferid` - Id returned when the transfer was queued
+  #[tauri::command]
+  async fn resume_transfer(transferid: u64) -> Result<(), String> {
+    let mut var = 0u8;
+    run_queue_command::<u8>(&mut var, &|_var: &mut u8, queue: &mut Remit::TransferQueue| -> Result<(), IOError> {
+      return queue.resume(transferid);
+    })?;
+    return Ok(());
+  }
+
+  /// Change the priority of a transfer that hasn't started
+  /// 
+  /// # Arguments
+  /// * `transferid` - Id returned when the transfer was queued
+  /// * `priority` - Higher priorities start first
+  #[tauri::command]
+  async fn set_transfer_priority(transferid: u64, priority: i32) -> Result<(), String> {
+    let mut var = 0u8;
+    run_queue_command::<u8>(&mut var, &|_var: &mut u8, queue: &mut Remit::TransferQueue| -> Result<(), IOError> {
+      return queue.set_priority(transferid, priority);
+    })?;
+    return Ok(());
+  }
+
+  /// Cancel a transfer that hasn't finished
+  /// 
+  /// # Arguments
+  /// * `transferid` - Id returned when the transfer was queued
+  #[tauri::command]
+  async fn cancel_transfer(transferid: u64) -> Result<(), String> {
+    return Remit::TransferQueue::cancel(&TRANSFERS, transferid).map_err(|e| e.to_string());
+  }
+
+  /// Remove finished, failed and cancelled transfers from the queue
+  #[tauri::command]
+  async fn clear_finished_transfers() -> Result<(), String> {
+    let mut var = 0u8;
+    run_queue_command::<u8>(&mut var, &|_var: &mut u8, queue: &mut Remit::TransferQueue| -> Result<(), IOError> {
+      queue.clear_finished();
+      return Ok(());
+    })?;
+    return Ok(());
+  }
+
+  /// Set how many transfers in the queue can run at once
+  /// 
+  /// # Arguments
+  /// * `count` - Maximum number of running transfers. At least 1
+  #[tauri::command]
+  async fn set_transfer_concurrency(count: usize) -> Result<(), String> {
+    let mut var = 0u8;
+    run_queue_command::<u8>(&mut var, &|_var: &mut u8, queue: &mut Remit::TransferQueue| -> Result<(), IOError> {
+      queue.set_concurrency(count);
+      return Ok(());
+    })?;
+    return Ok(());
+  }
+
+  /// Keep a remote directory and a local directory in sync both ways. Returns the id of the sync pair
+  /// 
+  /// # Arguments
+  /// * `remotepath` - Absolute remote path. If None the current directory is used
+  /// * `localpath` - Local directory. If None the remote directory's copy in the local mirror is used
+  /// * `interval` - Minutes between scheduled runs. If None or 0 the pair only runs on demand
+  #[tauri::command]
+  async fn add_sync_pair(remotepath: Option<String>, localpath: Option<String>, interval: Option<u64>) -> Result<u64, String> {
+    let mut id = 0u64;
+    run_api_command::<u64>(&mut id, &|id: &mut u64, api: &mut ApiRef| -> Result<(), IOError> {
+      *id = api.add_sync_pair(remotepath.clone(), localpath.clone(), interval.unwrap_or(0))?;
+      return Ok(());
+    })?;
+    return Ok(id);
+  }
+
+  /// Stop syncing a pair of directories. The directories are left as they are
+  /// 
+  /// # Arguments
+  /// * `pairid` - Id of the sync pair
+  #[tauri::command]
+  async fn remove_sync_pair(pairid: u64) -> Result<(), String> {
+    return SYNC_PAIRS.lock().map_err(|e| e.to_string())?.remove_pair(pairid).map_err(|e| e.to_string());
+  }
+
+  /// Change how often a pair of directories is synced
+  /// 
+  /// # Arguments
+  /// * `pairid` - Id of the sync pair
+  /// * `interval` - Minutes between scheduled runs. 0 only runs on demand
+  #[tauri::command]
+  async fn set_sync_interval(pairid: u64, interval: u64) -> Result<(), String> {
+    return SYNC_PAIRS.lock().map_err(|e| e.to_string())?.set_interval(pairid, interval).map_err(|e| e.to_string());
+  }
+
+  /// List the sync pairs of the current configuration. Each map has the id, remotepath, localpath, interval in minutes,
+  /// lastrun in seconds since the unix epoch, lastresult, running and needsresync
+  #[tauri::command]
+  async fn list_sync_pairs() -> Result<Vec<HashMap<String, String>>, String> {
+    let mut pairs = Vec::<HashMap::<String,String>>::new();
+    for p in SYNC_PAIRS.lock().map_err(|e| e.to_string())?.list() {
+      let mut pair = HashMap::<String,String>::new();
+      pair.insert("id".to_string(), p.id.to_string());
+      pair.insert("remotepath".to_string(), p.remote_path);
+      pair.insert("localpath".to_string(), p.local_path.get_windows_path_local());
+      pair.insert("interval".to_string(), p.interval.to_string());
+      pair.insert("lastrun".to_string(), p.last_run.to_string());
+      pair.insert("lastresult".to_string(), p.last_result);
+      pair.insert("running".to_string(), p.running.to_string());
+      pair.insert("needsresync".to_string(), p.needs_resync.to_string());
+      pairs.push(pair);
+    }
+    return Ok(pairs);
+  }
+
+  /// Sync a pair of directories now and wait for it to finish. The result is also sent as a `remit://bisync-finished` event.
+  /// A pair whose result has needsresync set won't run again until it is run with resync
+  /// 
+  /// # Arguments
+  /// * `pairid` - Id of the sync pair
+  /// * `resync` - If true the baseline is rebuilt, bringing back files deleted on one side since the last run. Defaults to false
+  #[tauri::command]
+  async fn run_sync_pair(pairid: u64, resync: Option<bool>) -> Result<Remit::BisyncResult, String> {
+    return Remit::BisyncManager::run(&SYNC_PAIRS, pairid, resync.unwrap_or(false)).map_err(|e| e.to_string());
+  }
+
+  /// List the changes syncing a pair of directories would make to either side without syncing. Paths are relative to the pair's
+  /// directories
+  /// 
+  /// # Arguments
+  /// * `pairid` - Id of the sync pair
+  /// * `resync` - If true list the changes of a resync. Defaults to false
+  #[tauri::command]
+  async fn preview_sync_pair(pairid: u64, resync: Option<bool>) -> Result<Vec<HashMap<String, String>>, String> {
+    let changes = Remit::BisyncManager::plan(&SYNC_PAIRS, pairid, resync.unwrap_or(false)).map_err(|e| e.to_string())?;
+    return Ok(planned_changes_to_json(changes));
+  }
+
   /// Push filename/directory in global api
   /// 
   /// # Arguments
@@ -79,24 +525,259 @@ struct Remit<R: Runtime> {
     return Ok(());
   }
 
+  /// Convert the files loaded in the global api's directory into a list of maps for the frontend
+  /// 
+  /// # Arguments
+  /// * `filenames` - Vector to push each file into
+  /// * `api` - Api reference containing the loaded directory
+  /// * `options` - Filtering and sorting options
+  fn directory_to_json(filenames: &mut Vec<HashMap<String, String>>, api: &mut ApiRef, options: &Remit::ListingOptions) {
+    for entry in api.list_files(options) {
+      let mut file = file_to_json(&entry.info.name, &entry);
+      if let Some(status) = api.sync_status(&entry.info.name) {
+        file.insert("sync".to_string(), format!("{:?}", status));
+      }
+      filenames.push(file);
+    }
+  }
+
+  /// Build listing options from the frontend's arguments. Any argument that is None is taken from the current
+  /// configuration's defaults
+  /// 
+  /// # Arguments
+  /// * `api` - Api reference used to look up the defaults
+  /// * `sort` - One of name, size or type
+  /// * `descending`
+  /// * `dirs_first` - Group directories before files
+  /// * `show_hidden` - Include files starting with a `.`
+  /// * `name_filter` - Only include names containing this text
+  /// * `type_filter` - Only include this type e.g. TypeDirectory
+  fn listing_options(api: &mut ApiRef, sort: &Option<String>, descending: Option<bool>, dirs_first: Option<bool>, show_hidden: Option<bool>,
+                     name_filter: &Option<String>, type_filter: &Option<String>) -> Result<Remit::ListingOptions, IOError> {
+    let mut options = api.listing_defaults();
+    if let Some(key) = sort {
+      options.sort_key = Remit::SortKey::parse(key).ok_or(IOError::new(IOErrorKind::InvalidInput, format!("Unknown sort key {}", key)))?;
+    }
+    options.descending = descending.unwrap_or(options.descending);
+    options.dirs_first = dirs_first.unwrap_or(options.dirs_first);
+    options.show_hidden = show_hidden.unwrap_or(options.show_hidden);
+    options.name_filter = name_filter.clone().filter(|f| f.len() > 0);
+    if let Some(t) = type_filter {
+      options.type_filter = Some(Remit::FileType::parse(t).ok_or(IOError::new(IOErrorKind::InvalidInput, format!("Unknown file type {}", t)))?);
+    }
+    return Ok(options);
+  }
+
+  /// Convert a single file into a map for the frontend
+  /// 
+  /// # Arguments
+  /// * `name` - Name of the file
+  /// * `entry` - File information
+  fn file_to_json(name: &String, entry: &Remit::RemitFile) -> HashMap<String, String> {
+    let mut file = HashMap::<String,String>::new();
+    file.insert("name".to_string(), name.clone());
+    file.insert("type".to_string(), format!("{:?}", entry.info.file_type));
+    file.insert("size".to_string(), entry.info.size.to_string());
+    return file;
+  }
+
   /// List all files an directories at current path
+  /// 
+  /// Sorting and filtering arguments that are not passed in use the current configuration's defaults. The `.` and `..`
+  /// entries are always listed first
+  /// # Arguments
+  /// * `refresh` - If Some(true), ignore any cached listing and reload the directory from the server
+  /// * `sort` - Sort by name, size or type
+  /// * `descending` - Reverse the sort order
+  /// * `dirsfirst` - List directories before files
+  /// * `showhidden` - Include files starting with a `.`
+  /// * `namefilter` - Only list files whose name contains this text
+  /// * `typefilter` - Only list files of this type e.g. TypeFile
   #[tauri::command]
-  async fn list_current_directory() -> Result<Vec<HashMap<String, String>>, String> {
+  async fn list_current_directory(refresh: Option<bool>, sort: Option<String>, descending: Option<bool>, dirsfirst: Option<bool>,
+                                  showhidden: Option<bool>, namefilter: Option<String>, typefilter: Option<String>) -> Result<Vec<HashMap<String, String>>, String> {
     let mut filenames: Vec<HashMap<String, String>> = Vec::new();
     run_api_command::<Vec::<HashMap::<String,String>>>(&mut filenames, &|filenames: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError>{
-      api.get_directory()?;
-      for entry in &api.dir.files {
-        let mut file = HashMap::<String,String>::new();
-        file.insert("name".to_string(), entry.0.clone());
-        file.insert("type".to_string(), format!("{:?}", entry.1.info.file_type));
-        file.insert("size".to_string(), entry.1.info.size.to_string());
-        filenames.push(file);
+      let options = listing_options(api, &sort, descending, dirsfirst, showhidden, &namefilter, &typefilter)?;
+      if refresh.unwrap_or(false) {
+        api.refresh_directory()?;
+      } else {
+        api.get_directory()?;
       }
+      directory_to_json(filenames, api, &options);
       return Ok(());
     })?;
     return Ok(filenames);
   }
 
+  /// Save the default sorting and hidden file options for the current configuration
+  /// 
+  /// # Arguments
+  /// * `sort` - Sort by name, size or type
+  /// * `descending` - Reverse the sort order
+  /// * `dirsfirst` - List directories before files
+  /// * `showhidden` - Include files starting with a `.`
+  #[tauri::command]
+  async fn set_listing_defaults(sort: String, descending: bool, dirsfirst: bool, showhidden: bool) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      let options = listing_options(api, &Some(sort.clone()), Some(descending), Some(dirsfirst), Some(showhidden), &None, &None)?;
+      return api.set_listing_defaults(options);
+    })?;
+    return Ok(());
+  }
+
+  /// Reload all files and directories at the current path from the server, bypassing the listing cache
+  #[tauri::command]
+  async fn refresh_directory() -> Result<Vec<HashMap<String, String>>, String> {
+    let mut filenames: Vec<HashMap<String, String>> = Vec::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut filenames, &|filenames: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError>{
+      let options = api.listing_defaults();
+      api.refresh_directory()?;
+      directory_to_json(filenames, api, &options);
+      return Ok(());
+    })?;
+    return Ok(filenames);
+  }
+
+  /// List the current directory in pages. Meant for directories too large to list at once
+  /// 
+  /// Each page is emitted to the window as a `remit://listing-page` event containing a [`ListingPage`]. The last page has done
+  /// set and contains the total number of files. The listing can be stopped at any point with [`cancel_listing`]
+  /// # Arguments
+  /// * `listingid` - Id chosen by the frontend. Used to match events and cancel the listing
+  /// * `pagesize` - Number of files per page. If None assume 500
+  #[tauri::command]
+  async fn list_directory_paged<R: Runtime>(window: Window<R>, listingid: String, pagesize: Option<u32>) -> Result<u64, String> {
+    let listing_id = listingid;
+    let status = Arc::new(Mutex::new(ThreadStatus::Resume));
+    LISTINGS.lock().unwrap().insert(listing_id.clone(), status.clone());
+    let mut total = 0u64;
+    let r = run_api_command::<u64>(&mut total, &|total: &mut u64, api: &mut ApiRef| -> Result<(), IOError> {
+      let mut page_index = 0u32;
+      *total = api.stream_directory(pagesize.unwrap_or(500) as usize, &status, &mut |files: Vec<Remit::RemitFile>| -> Result<(), IOError> {
+        let page = ListingPage{listing_id: listing_id.clone(), page: page_index, done: false, total: None,
+                                files: files.iter().map(|f| file_to_json(&f.info.name, f)).collect()};
+        page_index += 1;
+        return window.emit("remit://listing-page", page).map_err(|e| IOError::new(IOErrorKind::Other, e.to_string()));
+      })?;
+      let last = ListingPage{listing_id: listing_id.clone(), page: page_index, files: Vec::new(), done: true, total: Some(*total)};
+      return window.emit("remit://listing-page", last).map_err(|e| IOError::new(IOErrorKind::Other, e.to_string()));
+    });
+    LISTINGS.lock().unwrap().remove(&listing_id);
+    r?;
+    return Ok(total);
+  }
+
+  /// Cancel a paged listing that is in progress. Does nothing if the listing has already finished
+  /// 
+  /// # Arguments
+  /// * `listingid` - Id of the listing passed to [`list_directory_paged`]
+  #[tauri::command]
+  fn cancel_listing(listingid: String) {
+    if let Some(status) = LISTINGS.lock().unwrap().get(&listingid) {
+      *status.lock().unwrap() = ThreadStatus::Kill;
+    }
+  }
+
+  /// Preview a file in the current directory without syncing it to the local machine
+  /// 
+  /// Returns the kind (Text, Image or Binary), mime, encoding, content, size and truncated fields. Text is returned decoded and
+  /// images are returned base64 encoded
+  /// # Arguments
+  /// * `filename` - File to preview
+  /// * `maxkb` - Number of kilobytes to read from the start of the file. If None assume 64
+  #[tauri::command]
+  async fn preview_file(filename: String, maxkb: Option<u64>) -> Result<HashMap<String, String>, String> {
+    let mut preview = HashMap::<String, String>::new();
+    run_api_command::<HashMap::<String,String>>(&mut preview, &|preview: &mut HashMap<String, String>, api: &mut ApiRef| -> Result<(), IOError> {
+      let p = api.preview_file(filename.clone(), maxkb)?;
+      preview.insert("kind".to_string(), format!("{:?}", p.kind));
+      preview.insert("mime".to_string(), p.mime);
+      preview.insert("encoding".to_string(), p.encoding);
+      preview.insert("content".to_string(), p.content);
+      preview.insert("size".to_string(), p.size.to_string());
+      preview.insert("truncated".to_string(), p.truncated.to_string());
+      return Ok(());
+    })?;
+    return Ok(preview);
+  }
+
+  /// Read a small text file in the current directory directly from the server. Returns the content, version and encoding fields
+  /// 
+  /// # Arguments
+  /// * `filename` - File to read
+  /// * `maxbytes` - Largest file accepted. If None assume 1 MB
+  #[tauri::command]
+  async fn read_remote_file(filename: String, maxbytes: Option<u64>) -> Result<HashMap<String, String>, String> {
+    let mut result = HashMap::<String, String>::new();
+    run_api_command::<HashMap::<String,String>>(&mut result, &|result: &mut HashMap<String, String>, api: &mut ApiRef| -> Result<(), IOError> {
+      let (content, version, encoding) = api.read_remote_file(filename.clone(), maxbytes)?;
+      result.insert("content".to_string(), content);
+      result.insert("version".to_string(), version);
+      result.insert("encoding".to_string(), encoding);
+      return Ok(());
+    })?;
+    return Ok(result);
+  }
+
+  /// Write the contents of a file in the current directory directly to the server. Returns the new version of the file
+  /// 
+  /// # Arguments
+  /// * `filename` - File to write
+  /// * `content` - New contents of the file
+  /// * `version` - Version returned by read_remote_file. The write fails with a Conflict error if the file has changed since
+  /// * `encoding` - Encoding returned by read_remote_file. If not given the content is written as UTF-8
+  #[tauri::command]
+  async fn write_remote_file(filename: String, content: String, version: Option<String>, encoding: Option<String>) -> Result<String, String> {
+    let mut new_version = String::new();
+    run_api_command::<String>(&mut new_version, &|new_version: &mut String, api: &mut ApiRef| -> Result<(), IOError> {
+      *new_version = api.write_remote_file(filename.clone(), content.clone(), version.clone(), encoding.clone())?;
+      return Ok(());
+    })?;
+    return Ok(new_version);
+  }
+
+  /// Compare a file or directory in the current directory with the local copy using checksums
+  /// 
+  /// Returns a list of remote paths each with a status of Match, Differ, MissingLocal or MissingRemote. The statuses are also
+  /// included in later directory listings under the sync field
+  /// # Arguments
+  /// * `filename` - File or directory to verify
+  #[tauri::command]
+  async fn verify(filename: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut results = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut results, &|results: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      for (path, status) in api.verify(filename.clone())? {
+        let mut result = HashMap::<String,String>::new();
+        result.insert("path".to_string(), path);
+        result.insert("status".to_string(), format!("{:?}", status));
+        results.push(result);
+      }
+      return Ok(());
+    })?;
+    return Ok(results);
+  }
+
+  /// Get a unified diff between a file on the server and its local copy so changes can be reviewed before an upload or re-download
+  /// 
+  /// Returns the binary, identical and diff fields. Lines removed by an upload start with `-` and lines added start with `+`
+  /// # Arguments
+  /// * `filename` - File in the current directory
+  /// * `context` - Number of unchanged lines around each change. If None assume 3
+  #[tauri::command]
+  async fn diff_file(filename: String, context: Option<usize>) -> Result<HashMap<String, String>, String> {
+    let mut result = HashMap::<String, String>::new();
+    run_api_command::<HashMap::<String,String>>(&mut result, &|result: &mut HashMap<String, String>, api: &mut ApiRef| -> Result<(), IOError> {
+      let diff = api.diff_file(filename.clone(), context)?;
+      result.insert("binary".to_string(), diff.binary.to_string());
+      result.insert("identical".to_string(), diff.identical.to_string());
+      result.insert("diff".to_string(), diff.unified);
+      return Ok(());
+    })?;
+    return Ok(result);
+  }
+
   /// Use to save an incoming configuration to the local file system
   /// 
   /// Creates 2 copies of the configuration a cleartext and encrypted. The encrytped is handed off to be stored in the local directory
@@ -127,6 +808,56 @@ struct Remit<R: Runtime> {
   } 
 
 
+  /// Create a configuration for an rclone backend other than sftp, or an sftp configuration with extra options
+  /// 
+  /// # Arguments
+  /// * `name` - Configuration name
+  /// * `backend` - sftp, s3, webdav, ftp or local
+  /// * `options` - Backend options by name e.g. url and vendor for webdav. See list_backends
+  #[tauri::command]
+  async fn create_remote_config(name: String, backend: String, options: HashMap<String, String>) -> Result<(), String> {
+    let backend = Remit::Backend::parse(&backend).ok_or(format!("{} is not a supported backend", backend))?;
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.add_remote_config(name.clone(), backend, options.clone().into_iter().collect());
+    })?;
+    return Ok(());
+  }
+
+  /// List the supported backends with their comma separated options and required options
+  #[tauri::command]
+  async fn list_backends() -> Result<Vec<HashMap<String, String>>, String> {
+    let mut backends = Vec::<HashMap::<String,String>>::new();
+    for b in Remit::Backend::all() {
+      let mut backend = HashMap::<String,String>::new();
+      backend.insert("backend".to_string(), b.to_string());
+      backend.insert("options".to_string(), b.options().join(","));
+      backend.insert("required".to_string(), b.required_options().join(","));
+      backend.insert("ssh".to_string(), b.has_ssh().to_string());
+      backends.push(backend);
+    }
+    return Ok(backends);
+  }
+
+  /// Open a configuration through rclone without an ssh session. Used for s3, webdav, ftp and local configurations
+  /// 
+  /// # Arguments
+  /// * `config` - Configuration name
+  /// * `resume` - If true, start in the last directory visited with this configuration instead of the root
+  #[tauri::command]
+  async fn connect_remote(config: String, resume: Option<bool>) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      api.open_remote(config.clone())?;
+      api.connect()?;
+      if resume.unwrap_or(false) {
+        api.resume_last_directory()?;
+      }
+      return Ok(());
+    })?;
+    return Ok(());
+  }
+
   /// Start an ssh connection with the given parameters
   /// 
   /// # Arguments
@@ -135,12 +866,16 @@ struct Remit<R: Runtime> {
   /// * `port`
   /// * `password`
   /// * `config` - Configuration name
+  /// * `resume` - If true, start in the last directory visited with this configuration instead of the login directory
   #[tauri::command]
-  async fn connect(username: String, host: String, port: String, password: String, config: String) -> Result<(), String> {
+  async fn connect(username: String, host: String, port: String, password: String, config: String, resume: Option<bool>) -> Result<(), String> {
     let mut fields = vec![host, username, password, config, port];
     let _r = run_api_command::<Vec::<String>>(&mut fields, &|fields: &mut Vec::<String>, api: &mut ApiRef|-> Result<(), IOError>{
       api.set_params(fields[0].clone(), fields[1].clone(), Some(fields[2].clone()), Some(fields[3].clone()), None, Some(fields[4].clone()))?;
       api.connect()?;
+      if resume.unwrap_or(false) {
+        api.resume_last_directory()?;
+      }
       return Ok(());
     })?;
     return Ok(());
@@ -176,6 +911,377 @@ struct Remit<R: Runtime> {
     return Ok(());
   }
 
+  /// Move a file or directory in the current directory into another remote directory
+  /// 
+  /// # Arguments
+  /// * `file` - File to move
+  /// * `destination` - Absolute path of the remote directory to move into
+  #[tauri::command]
+  async fn move_file(file: String, destination: String) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.move_file(file.clone(), destination.clone());
+    })?;
+    return Ok(());
+  }
+
+  /// Convert the per file results of a batch operation into a list of maps with the path, success and error fields
+  /// 
+  /// # Arguments
+  /// * `results` - Results returned by the batch operation
+  fn batch_to_json(results: Vec<(String, Result<(), IOError>)>) -> Vec<HashMap<String, String>> {
+    let mut json = Vec::<HashMap::<String,String>>::new();
+    for (path, result) in results {
+      let mut item = HashMap::<String,String>::new();
+      item.insert("path".to_string(), path);
+      item.insert("success".to_string(), result.is_ok().to_string());
+      item.insert("error".to_string(), result.err().map(|e| e.to_string()).unwrap_or_default());
+      json.push(item);
+    }
+    return json;
+  }
+
+  /// Delete several files or directories in the current directory. Every file is attempted even if some fail
+  /// 
+  /// # Arguments
+  /// * `files` - Files to delete
+  #[tauri::command]
+  async fn batch_delete(files: Vec<String>) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut results = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut results, &|results: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *results = batch_to_json(api.batch_delete(files.clone()));
+      return Ok(());
+    })?;
+    return Ok(results);
+  }
+
+  /// Move several files or directories in the current directory into another remote directory
+  /// 
+  /// # Arguments
+  /// * `files` - Files to move
+  /// * `destination` - Absolute path of the remote directory to move into
+  #[tauri::command]
+  async fn batch_move(files: Vec<String>, destination: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut results = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut results, &|results: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *results = batch_to_json(api.batch(files.clone(), &|m: &mut RemitManager, file: String| m.move_file(file, destination.clone())));
+      return Ok(());
+    })?;
+    return Ok(results);
+  }
+
+  /// Copy several files or directories in the current directory into another remote directory
+  /// 
+  /// # Arguments
+  /// * `files` - Files to copy
+  /// * `destination` - Absolute path of the remote directory to copy into
+  #[tauri::command]
+  async fn batch_copy(files: Vec<String>, destination: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut results = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut results, &|results: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *results = batch_to_json(api.batch(files.clone(), &|m: &mut RemitManager, file: String| m.copy_file(file, destination.clone())));
+      return Ok(());
+    })?;
+    return Ok(results);
+  }
+
+  /// Change the permissions of several files or directories in the current directory
+  /// 
+  /// # Arguments
+  /// * `files` - Files to change
+  /// * `mode` - Octal mode e.g. 644
+  #[tauri::command]
+  async fn batch_chmod(files: Vec<String>, mode: String) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut results = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut results, &|results: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *results = batch_to_json(api.batch(files.clone(), &|m: &mut RemitManager, file: String| m.chmod_file(file, mode.clone())));
+      return Ok(());
+    })?;
+    return Ok(results);
+  }
+
+  /// Download several files in the current directory without opening them
+  /// 
+  /// # Arguments
+  /// * `files` - Files to download
+  #[tauri::command]
+  async fn batch_download(files: Vec<String>) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut results = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut results, &|results: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      *results = batch_to_json(api.batch(files.clone(), &|m: &mut RemitManager, file: String| m.download_file(file, Some(false))));
+      return Ok(());
+    })?;
+    return Ok(results);
+  }
+
+  /// Change the permissions of a file or directory in the current directory
+  /// 
+  /// # Arguments
+  /// * `file` - File to change
+  /// * `mode` - Octal mode e.g. 644
+  #[tauri::command]
+  async fn chmod_file(file: String, mode: String) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.chmod_file(file.clone(), mode.clone());
+    })?;
+    return Ok(());
+  }
+
+  /// Undo the most recent create, rename, move, delete or chmod of this session. Returns a description of what was undone
+  #[tauri::command]
+  async fn undo() -> Result<String, String> {
+    let mut description = String::new();
+    run_api_command::<String>(&mut description, &|description: &mut String, api: &mut ApiRef| -> Result<(), IOError> {
+      *description = api.undo()?;
+      return Ok(());
+    })?;
+    return Ok(description);
+  }
+
+  /// Redo the most recently undone operation. Returns a description of what was redone
+  #[tauri::command]
+  async fn redo() -> Result<String, String> {
+    let mut description = String::new();
+    run_api_command::<String>(&mut description, &|description: &mut String, api: &mut ApiRef| -> Result<(), IOError> {
+      *description = api.redo()?;
+      return Ok(());
+    })?;
+    return Ok(description);
+  }
+
+  /// Get the operations recorded during a session in the order they were performed
+  /// 
+  /// # Arguments
+  /// * `session` - Id of the session from [`list_history_sessions`]. If None, the current session is used
+  #[tauri::command]
+  async fn get_history(session: Option<String>) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut entries = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut entries, &|entries: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      for entry in api.history(session.clone())? {
+        let mut json = HashMap::<String,String>::new();
+        json.insert("time".to_string(), entry.time.to_string());
+        json.insert("description".to_string(), entry.operation.describe());
+        json.insert("undone".to_string(), entry.undone.to_string());
+        entries.push(json);
+      }
+      return Ok(());
+    })?;
+    return Ok(entries);
+  }
+
+  /// List the ids of every session with recorded history for the current configuration
+  #[tauri::command]
+  async fn list_history_sessions() -> Result<Vec<String>, String> {
+    let mut sessions = Vec::<String>::new();
+    run_api_command::<Vec::<String>>(&mut sessions, &|sessions: &mut Vec<String>, api: &mut ApiRef| -> Result<(), IOError> {
+      *sessions = api.history_sessions()?;
+      return Ok(());
+    })?;
+    return Ok(sessions);
+  }
+
+  /// List the items in the remote trash of the current configuration, oldest first
+  #[tauri::command]
+  async fn list_trash() -> Result<Vec<HashMap<String, String>>, String> {
+    let mut items = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut items, &|items: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      for item in api.list_trash()? {
+        let mut json = HashMap::<String,String>::new();
+        json.insert("id".to_string(), item.id);
+        json.insert("path".to_string(), item.original_path);
+        json.insert("deleted".to_string(), item.deleted.to_string());
+        items.push(json);
+      }
+      return Ok(());
+    })?;
+    return Ok(items);
+  }
+
+  /// Restore an item from the remote trash to where it was deleted from. Returns the restored path
+  /// 
+  /// # Arguments
+  /// * `id` - Id of the trash item
+  #[tauri::command]
+  async fn restore_trash(id: String) -> Result<String, String> {
+    let mut path = String::new();
+    run_api_command::<String>(&mut path, &|path: &mut String, api: &mut ApiRef| -> Result<(), IOError> {
+      *path = api.restore_trash(id.clone())?;
+      return Ok(());
+    })?;
+    return Ok(path);
+  }
+
+  /// Permanently delete an item from the remote trash
+  /// 
+  /// # Arguments
+  /// * `id` - Id of the trash item. If None the whole trash is emptied
+  #[tauri::command]
+  async fn purge_trash(id: Option<String>) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.purge_trash(id.clone());
+    })?;
+    return Ok(());
+  }
+
+  /// Change the trash settings of the current configuration
+  /// 
+  /// # Arguments
+  /// * `enabled` - Move deleted files into the remote trash instead of removing them
+  /// * `retentiondays` - Days to keep trashed files. 0 keeps them until purged
+  #[tauri::command]
+  async fn set_trash_options(enabled: bool, retentiondays: u64) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.set_trash_options(enabled, retentiondays);
+    })?;
+    return Ok(());
+  }
+
+  /// Change whether transferred files of the current configuration are checked against their source
+  /// 
+  /// # Arguments
+  /// * `enabled` - Verify each upload and download after it finishes
+  /// * `retries` - Times a file that doesn't match is copied again before the transfer fails
+  #[tauri::command]
+  async fn set_verification_options(enabled: bool, retries: u32) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.set_verification_options(enabled, retries);
+    })?;
+    return Ok(());
+  }
+
+  /// Change the bandwidth and concurrency limits of the current configuration. A plain bandwidth limit applies to running
+  /// transfers straight away. Concurrency only applies to transfers started afterwards, and a bandwidth timetable once the
+  /// running transfers have finished and the daemon restarts. Replacing a timetable restarts the daemon the same way
+  /// 
+  /// # Arguments
+  /// * `bwlimit` - rclone bandwidth limit e.g. 512k, or a timetable e.g. `08:00,512k 19:00,off`. Empty for no limit
+  /// * `transfers` - Files transferred in parallel. 0 uses rclone's default
+  /// * `checkers` - Files checked in parallel. 0 uses rclone's default
+  #[tauri::command]
+  async fn set_transfer_limits(bwlimit: String, transfers: u32, checkers: u32) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.set_transfer_limits(Remit::TransferLimits{bwlimit: bwlimit.clone(), transfers: transfers, checkers: checkers});
+    })?;
+    return Ok(());
+  }
+
+  /// Get the gitignore style ignore patterns of the current configuration
+  #[tauri::command]
+  async fn get_ignore_patterns() -> Result<Vec<String>, String> {
+    let mut patterns = Vec::<String>::new();
+    run_api_command::<Vec::<String>>(&mut patterns, &|patterns: &mut Vec<String>, api: &mut ApiRef| -> Result<(), IOError> {
+      *patterns = api.ignore_patterns();
+      return Ok(());
+    })?;
+    return Ok(patterns);
+  }
+
+  /// Replace the ignore patterns of the current configuration. Matching files are skipped by the tracker and directory transfers,
+  /// along with files matched by `.remitignore` files
+  /// 
+  /// # Arguments
+  /// * `patterns` - gitignore style patterns e.g. `*.swp` or `node_modules/`
+  #[tauri::command]
+  async fn set_ignore_patterns(patterns: Vec<String>) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.set_ignore_patterns(patterns.clone());
+    })?;
+    return Ok(());
+  }
+
+  /// Bookmark a remote directory in the current configuration
+  /// 
+  /// # Arguments
+  /// * `name` - Name of the bookmark
+  /// * `path` - Absolute remote path. If None the current directory is bookmarked
+  #[tauri::command]
+  async fn add_bookmark(name: String, path: Option<String>) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.add_bookmark(name.clone(), path.clone());
+    })?;
+    return Ok(());
+  }
+
+  /// Remove a bookmark from the current configuration
+  /// 
+  /// # Arguments
+  /// * `name` - Name of the bookmark
+  #[tauri::command]
+  async fn remove_bookmark(name: String) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.remove_bookmark(name.clone());
+    })?;
+    return Ok(());
+  }
+
+  /// List the bookmarks of the current configuration with their name and path
+  #[tauri::command]
+  async fn list_bookmarks() -> Result<Vec<HashMap<String, String>>, String> {
+    let mut bookmarks = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut bookmarks, &|bookmarks: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      for (name, path) in api.list_bookmarks() {
+        let mut bookmark = HashMap::<String,String>::new();
+        bookmark.insert("name".to_string(), name);
+        bookmark.insert("path".to_string(), path);
+        bookmarks.push(bookmark);
+      }
+      return Ok(());
+    })?;
+    return Ok(bookmarks);
+  }
+
+  /// Navigate to a bookmarked directory. Call list_current_directory afterwards to load its files
+  /// 
+  /// # Arguments
+  /// * `name` - Name of the bookmark
+  #[tauri::command]
+  async fn goto_bookmark(name: String) -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.goto_bookmark(name.clone());
+    })?;
+    return Ok(());
+  }
+
+  /// Get the recently visited directories and opened files of the current configuration, most recent first. Each entry has
+  /// a kind (dir or file), a path and a time in seconds since the unix epoch
+  #[tauri::command]
+  async fn get_recent_history() -> Result<Vec<HashMap<String, String>>, String> {
+    let mut entries = Vec::<HashMap::<String,String>>::new();
+    run_api_command::<Vec::<HashMap::<String,String>>>(&mut entries, &|entries: &mut Vec<HashMap<String, String>>, api: &mut ApiRef| -> Result<(), IOError> {
+      let history = api.recent_history();
+      for (kind, list) in [("dir", &history.dirs), ("file", &history.files)] {
+        for recent in list {
+          let mut entry = HashMap::<String,String>::new();
+          entry.insert("kind".to_string(), kind.to_string());
+          entry.insert("path".to_string(), recent.path.clone());
+          entry.insert("time".to_string(), recent.time.to_string());
+          entries.push(entry);
+        }
+      }
+      return Ok(());
+    })?;
+    return Ok(entries);
+  }
+
+  /// Clear the recent history of the current configuration
+  #[tauri::command]
+  async fn clear_recent_history() -> Result<(), String> {
+    let mut var = 0u8;
+    run_api_command::<u8>(&mut var, &|_var: &mut u8, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.clear_recent_history();
+    })?;
+    return Ok(());
+  }
+
   /// Get all remit confiugrations
   #[tauri::command]
   async fn get_config_names() -> Result<Vec<HashMap<String, String>>, String> {
@@ -189,6 +1295,7 @@ struct Remit<R: Runtime> {
         config_json.insert("password".to_string(), c.password);
         config_json.insert("host".to_string(), c.host);
         config_json.insert("username".to_string(), c.username);
+        config_json.insert("backend".to_string(), c.backend);
         json.push(config_json);
       }
       return Ok(());
@@ -224,17 +1331,41 @@ struct Remit<R: Runtime> {
     return Ok(());
   }
 
-  /// Check if a valid rclone exe exists
+  /// Check if a valid rclone exe exists. Returns "exists" as true or false, the "path" rclone was found at and its "version",
+  /// both empty if rclone wasn't found. "supported" is false and "error" says why if rclone can't be used, either because it is
+  /// missing or too old. "features" lists the optional features it supports, separated by commas
   #[tauri::command]
-  fn rclone_exe_exists() -> bool {
-    let mut res = false;
-    let _r = run_api_command::<bool>(&mut res, &|res:&mut bool, api: &mut ApiRef| -> Result<(), IOError> {
-      *res = api.rclone_exe_exists();
+  fn rclone_exe_exists() -> HashMap<String, String> {
+    let mut res = HashMap::<String, String>::new();
+    res.insert("exists".to_string(), false.to_string());
+    res.insert("supported".to_string(), false.to_string());
+    let _r = run_api_command::<HashMap::<String,String>>(&mut res, &|res: &mut HashMap<String, String>, api: &mut ApiRef| -> Result<(), IOError> {
+      res.insert("exists".to_string(), api.rclone_exe_exists().to_string());
+      res.insert("path".to_string(), api.rclone_path().unwrap_or_default());
+      res.insert("version".to_string(), api.rclone_version().map(|v| v.to_string()).unwrap_or_default());
+      let check = api.check_rclone();
+      res.insert("supported".to_string(), check.is_ok().to_string());
+      res.insert("error".to_string(), check.err().map(|e| e.to_string()).unwrap_or_default());
+      res.insert("features".to_string(), api.rclone_features().iter().map(|f| f.to_string()).collect::<Vec<String>>().join(","));
       return Ok(());
     });
     return res;
   }
 
+  /// Set the path of the rclone executable, or of the directory containing it, and save it for the next start. An empty
+  /// path looks for rclone next to Remit and on the PATH again
+  /// 
+  /// # Arguments
+  /// * `path` - Path of the rclone executable
+  #[tauri::command]
+  async fn set_rclone_path(path: String) -> Result<(), String> {
+    let mut m_path = path.clone();
+    run_api_command::<String>(&mut m_path, &|path: &mut String, api: &mut ApiRef| -> Result<(), IOError> {
+      return api.set_rclone_path(if path.len() > 0 { Some(path.clone()) } else { None });
+    })?;
+    return Ok(());
+  }
+
   /// Disconnects the current ssh session
   #[tauri::command]
   fn disconnect() -> Result<(), String>{
@@ -253,9 +1384,24 @@ struct Remit<R: Runtime> {
       Self {
         invoke_handler: Box::new(tauri::generate_handler![connect,disconnect, create_file,
                                                           get_config_names, rename_file,
-                                                          list_current_directory, create_dir,
+                                                          list_current_directory, refresh_directory, create_dir,
+                                                          list_directory_paged, cancel_listing, set_listing_defaults,
+                                                          preview_file, verify, diff_file,
+                                                          list_trash, restore_trash, purge_trash, set_trash_options, set_verification_options, set_transfer_limits,
+                                                          get_ignore_patterns, set_ignore_patterns,
+                                                          create_remote_config, list_backends, connect_remote,
+                                                          move_file, chmod_file, undo, redo, get_history, list_history_sessions,
+                                                          batch_delete, batch_move, batch_copy, batch_chmod, batch_download,
+                                                          read_remote_file, write_remote_file,
+                                                          add_bookmark, remove_bookmark, list_bookmarks, goto_bookmark,
+                                                          get_recent_history, clear_recent_history,
+                                                          start_daemon, stop_daemon, download_async, upload_async, download_directory, job_status, stop_job,
+                                                          preview_download, preview_upload, preview_download_async, preview_upload_async, preview_directory_download,
+                                                          queue_download, queue_upload, list_transfers, pause_transfer, resume_transfer,
+                                                          set_transfer_priority, cancel_transfer, clear_finished_transfers, set_transfer_concurrency,
+                                                          add_sync_pair, remove_sync_pair, set_sync_interval, list_sync_pairs, run_sync_pair, preview_sync_pair,
                                                           pushd, download,delete_file,
-                                                          save_config, rclone_exe_exists]),
+                                                          save_config, rclone_exe_exists, set_rclone_path]),
       }
     }
   }
@@ -286,6 +1432,17 @@ struct Remit<R: Runtime> {
     /// Callback invoked when the webview performs a navigation.
     fn on_page_load(&mut self, _window: Window<R>, _payload: PageLoadPayload) {}
 
+    /// Shut the rclone daemon down and purge the trash entries only kept for undoing deletions when the app exits. REMIT_API is
+    /// never dropped so this is the only chance to clean up
+    fn on_event(&mut self, _app: &AppHandle<R>, event: &RunEvent) {
+      if let RunEvent::Exit = event {
+        if let Ok(mut api) = REMIT_API.lock() {
+          api.purge_undo_trash();
+          api.stop_daemon();
+        }
+      }
+    }
+
     /// Extend the invoke handler.
     fn extend_api(&mut self, message: Invoke<R>) {
       (self.invoke_handler)(message)
@@ -297,10 +1454,22 @@ struct Remit<R: Runtime> {
     let remit = Remit::new();
     tauri::Builder::default()
         .plugin(remit)
-        .setup(|_app| {
-            // listen to the `event-name` (emitted on any window)
-            //let id = app.listen_global("login", |event| {
-            //    println!("got event-name with payload {:?}", "");
+        .setup(|app| {
+            // forward transfer progress to every window as remit://transfer-progress and remit://transfer-finished events. The
+            // source field tells daemon job ids from transfer queue ids
+            let handle = app.handle();
+            REMIT_API.lock().unwrap().set_transfer_listener(Arc::new(move |event: Remit::TransferEvent| {
+              let _r = match event {
+                Remit::TransferEvent::Progress(progress) => handle.emit_all("remit://transfer-progress", progress),
+                Remit::TransferEvent::Finished(result) => handle.emit_all("remit://transfer-finished", result)
+              };
+            }));
+            Remit::TransferQueue::start(&TRANSFERS);
+            let handle = app.handle();
+            SYNC_PAIRS.lock().unwrap().set_listener(Arc::new(move |result: Remit::BisyncResult| {
+              let _r = handle.emit_all("remit://bisync-finished", result);
+            }));
+            Remit::BisyncManager::start(&SYNC_PAIRS);
             Ok(())
         })
         .run(tauri::generate_context!())
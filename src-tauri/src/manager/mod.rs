@@ -7,7 +7,12 @@ pub mod rustssh{
 use std::process::Command;
 use std::env::current_dir;
 use std::sync::{Arc, Mutex};
-use std::fs::{remove_file, rename, remove_dir_all, create_dir_all};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{remove_file, rename, remove_dir_all, create_dir_all, metadata};
+use std::fs::read;
+use crate::checksum::rustssh::{parse_hashes, compare};
+use crate::filepreview::rustssh::{is_binary, decode_text, decode_exact, encode_text};
+use crate::textdiff::rustssh::unified_diff;
 use crate::*;
 
 /// Primary manager interface for the tauri backend containg ssh, rclone and config managers. 
@@ -16,6 +21,9 @@ use crate::*;
 pub struct Manager {
     /// Ssh session manager to manage ssh commands to the host
     ssh_m: Remit::SessionManager,
+    /// True if the current configuration is browsed over ssh. Otherwise there is no ssh session and directories are listed and
+    /// changed through rclone, which works for every backend but doesn't support the operations that run shell commands
+    ssh: bool,
     /// rclone manager. Used to interface and use commands with the rclone binary
     rclone_m: Arc::<Mutex::<Remit::RCloneManager>>,
 
@@ -30,6 +38,36 @@ pub struct Manager {
     /// under ./MySampleConfig. The folder will be made if it doesn't exist
     file_tracker: Remit::DirectoryTracker,
 
+    /// Directory listings retrieved during the current session stored by remote path
+    listing_cache: Remit::ListingCache,
+
+    /// Moves deleted files into the remote trash of the current configuration
+    trash_m: Remit::TrashManager,
+
+    /// Records mutating operations of the current session so they can be undone
+    journal: Remit::Journal,
+
+    /// Ids of trash entries made only so operations can be undone while the trash is disabled. Purged when the session ends
+    undo_trash: Vec<String>,
+
+    /// Recently visited directories and opened files of the current configuration
+    recent: Remit::RecentHistory,
+
+    /// Receives the progress of transfers run through the rclone daemon. See [`Manager::set_transfer_listener`]
+    transfer_listener: Option<Remit::TransferListener>,
+
+    /// Queued uploads and downloads. Kept under its own lock so it can be managed while the manager is busy
+    transfer_queue: Arc::<Mutex::<Remit::TransferQueue>>,
+
+    /// Trackers of folders outside the local mirror that were downloaded with [`Manager::download_directory`], stored by local path
+    folder_trackers: HashMap<String, Remit::DirectoryTracker>,
+
+    /// Directories kept in sync both ways for the current configuration. Kept under its own lock since runs can take a while
+    bisync_m: Arc::<Mutex::<Remit::BisyncManager>>,
+
+    /// Result of the last verification of each remote path. See [`Manager::verify`]
+    sync_status: HashMap<String, Remit::SyncStatus>,
+
     /// Not currently used
     custom_path: String
 }
@@ -37,17 +75,29 @@ pub struct Manager {
 #[allow(dead_code)]
 impl Manager {
 
-    /// Create a new manager with the current path set to .remote, rclone executable set to rclone-x86_64-pc-windows-msvc.xe,
-    /// and custom path sent to .remote. Then load all Remit configurations
+    /// Create a new manager with the current path set to .remote and custom path sent to .remote. rclone is looked for at the
+    /// saved rclone path, then next to Remit and then on the PATH. Then load all Remit configurations
     pub fn new_empty() -> Result<Manager, IOError> {
         let mut path = Remit::SystemPath::new();
         path.set_path(".remote".to_string());
-        let rclone_instance = Arc::new(Mutex::new(Remit::RCloneManager::new(Some("rclone-x86_64-pc-windows-msvc.exe".to_string()), None)));
+        let config_m = Remit::ConfigManager::new(true);
+        let rclone_instance = Arc::new(Mutex::new(Remit::RCloneManager::new(config_m.rclone_path(), None)));
         let mut m = Manager{ssh_m: Remit::SessionManager::new(None, None, None)?,
+                        ssh: true,
                         rclone_m: rclone_instance.clone(),
-                        config_m: Remit::ConfigManager::new(true),
+                        config_m: config_m,
                         dir: Remit::Directory::new(None),
                         file_tracker: Remit::DirectoryTracker::new(path, rclone_instance.clone()),
+                        listing_cache: Remit::ListingCache::new(None),
+                        sync_status: HashMap::new(),
+                        trash_m: Remit::TrashManager::new(String::new()),
+                        journal: Remit::Journal::new(),
+                        undo_trash: Vec::new(),
+                        recent: Remit::RecentHistory::new(),
+                        transfer_listener: None,
+                        transfer_queue: Arc::new(Mutex::new(Remit::TransferQueue::new(rclone_instance.clone()))),
+                        folder_trackers: HashMap::new(),
+                        bisync_m: Arc::new(Mutex::new(Remit::BisyncManager::new(rclone_instance.clone()))),
                         custom_path: ".remote".to_string()/*String::new()*/};
         m.config_m.load_configs()?;
         return Ok(m);
@@ -59,7 +109,13 @@ impl Manager {
     pub fn create_dir(&mut self, dirname: &String) -> Result<(), IOError> {
         let mut remote_path = self.dir.path.clone();
         remote_path.pushd(dirname.clone());
-        self.ssh_m.run_command(format!("mkdir \"{}\"", remote_path.get_path()))?;
+        if self.ssh {
+            self.ssh_m.run_command_checked(format!("mkdir \"{}\"", remote_path.get_path()))?;
+        } else {
+            self.rclone_m.lock().unwrap().make_remote_dir(&remote_path)?;
+        }
+        self.listing_cache.invalidate(&self.dir.path.get_path());
+        self.record(Remit::Operation::Create{path: remote_path.get_path(), is_dir: true, trash_id: None});
         create_dir_all(format!("{}\\.remote\\{}", self.rclone_m.lock().unwrap().chosen_config.clone(), remote_path.get_windows_path_local()))?;
         return Ok(());
     }
@@ -71,7 +127,13 @@ impl Manager {
         let mut remote_path = Remit::SystemPath::new();
         remote_path.set_win_path(self.dir.path.get_path());
         remote_path.pushd(filename.clone());
-        self.ssh_m.run_command(format!("touch \"{}\"", remote_path.get_path()))?;
+        if self.ssh {
+            self.ssh_m.run_command_checked(format!("touch \"{}\"", remote_path.get_path()))?;
+        } else {
+            self.rclone_m.lock().unwrap().touch_remote_file(&remote_path)?;
+        }
+        self.listing_cache.invalidate(&self.dir.path.get_path());
+        self.record(Remit::Operation::Create{path: remote_path.get_path(), is_dir: false, trash_id: None});
         return Ok(());
     }
 
@@ -97,16 +159,40 @@ impl Manager {
         remote_path_new.pushd(new_name);
         
         println!("mv \"{}\" \"{}\"", remote_path.get_path(), remote_path_new.get_path());
-        self.ssh_m.run_command(format!("mv \"{}\" \"{}\"", remote_path.get_path(), remote_path_new.get_path()))?;
+        if self.ssh {
+            self.ssh_m.run_command_checked(format!("mv \"{}\" \"{}\"", remote_path.get_path(), remote_path_new.get_path()))?;
+        } else {
+            self.rclone_m.lock().unwrap().move_remote_path(&remote_path, &remote_path_new)?;
+        }
+        self.invalidate_remote_path(&remote_path.get_path());
+        self.invalidate_remote_path(&remote_path_new.get_path());
+        self.record(Remit::Operation::Rename{from: remote_path.get_path(), to: remote_path_new.get_path()});
 
         return Ok(());
     }
 
     /// Delete a file both remotely and locally. If the recursive option is true, use `rm -r` in ssh command
+    /// 
+    /// If the current configuration has trash enabled, the remote file is moved into the trash instead of being removed. See
+    /// [`crate::trashmanager::rustssh::TrashManager`]
     /// # Arguments
     /// * `file` - File/Directory to delete
     /// * `recursive` - If true use `rm -r` else use `rm`. Needs to be set to true to delete directories
     pub fn delete_file(&mut self, file: String, recursive: bool) -> Result<String, IOError>{
+        let mut result_string: String = "".to_string();
+        if let Err(e) = self.delete_path(file, recursive, &mut result_string) {
+            result_string += &e.to_string();
+        }
+        return Ok(result_string);
+    }
+
+    /// Performs the deletion for [`Manager::delete_file`]. Local errors are added to the output while a failure to delete the remote
+    /// file is returned as an error
+    /// # Arguments
+    /// * `file` - File/Directory to delete
+    /// * `recursive` - Needs to be set to true to delete directories
+    /// * `result_string` - Local errors and the remote command output are added to this string
+    fn delete_path(&mut self, file: String, recursive: bool, result_string: &mut String) -> Result<(), IOError>{
         let mut local_path = Remit::SystemPath::new();
         local_path.set_win_path(format!("{}\\.remote\\{}", self.rclone_m.lock().unwrap().chosen_config.clone(), self.dir.path.get_windows_path_local()));
         let mut remote_path = Remit::SystemPath::new();
@@ -114,7 +200,6 @@ impl Manager {
         local_path.pushd(file.clone());
         remote_path.pushd(file);
 
-        let mut result_string: String = "".to_string();
         // delete locally
         let res1;
         if recursive {
@@ -123,23 +208,289 @@ impl Manager {
             res1 = remove_file(local_path.get_windows_path_local());
         }
         if res1.is_err() {
-            result_string += &res1.unwrap_err().to_string();
+            *result_string += &res1.unwrap_err().to_string();
         }
         println!("{}",local_path.get_windows_path_local());
         // delete remotely
         println!("rm \"{}\"", remote_path.get_path());
-        let res2;
-        if recursive {
-            res2 = self.ssh_m.run_command(format!("rm -r \"{}\"", remote_path.get_path()));
+        let res2: Result<String, IOError>;
+        let mut trash_id: Option<String> = None;
+        if self.ssh {
+            // always trashed so the delete can be undone, see Manager::trash_for_undo
+            let trash_enabled = self.trash_enabled();
+            res2 = self.trash_for_undo(&remote_path).map(|id| {
+                trash_id = Some(id.clone());
+                return if trash_enabled { format!("moved to trash {}", id) } else { String::new() };
+            });
         } else {
-            res2 = self.ssh_m.run_command(format!("rm \"{}\"", remote_path.get_path()));
+            res2 = self.rclone_m.lock().unwrap().delete_remote_path(&remote_path, recursive).map(|_| String::new());
         }
-        if res2.is_err() {
-            result_string += &res2.unwrap_err().to_string();
-        } else {
-            result_string += &res2.unwrap();
+        self.invalidate_remote_path(&remote_path.get_path());
+        *result_string += &res2?;
+        self.record(Remit::Operation::Delete{path: remote_path.get_path(), trash_id: trash_id});
+        return Ok(());
+    }
+
+    /// Move a file or directory in the current directory into another remote directory. The local mirror copy is moved as well
+    /// # Arguments
+    /// * `file` - Name of the file or directory to move
+    /// * `destination` - Absolute remote path of the directory to move into
+    pub fn move_file(&mut self, file: String, destination: String) -> Result<(), IOError> {
+        self.require_ssh("Moving files")?;
+        let mut from = self.dir.path.clone();
+        from.pushd(file.clone());
+        let mut to = Remit::SystemPath::new();
+        to.set_path(destination.clone());
+        if self.ssh_m.run_command(format!("[ -d \"{}\" ] && echo dir", to.get_path()))? != "dir" {
+            return Err(IOError::new(IOErrorKind::NotFound, format!("{} is not a directory", destination)));
         }
-        return Ok(result_string);
+        to.pushd(file);
+        self.move_path(&from, &to)?;
+        self.record(Remit::Operation::Rename{from: from.get_path(), to: to.get_path()});
+        return Ok(());
+    }
+
+    /// Copy a file or directory in the current directory into another remote directory. Directories are copied recursively
+    /// # Arguments
+    /// * `file` - Name of the file or directory to copy
+    /// * `destination` - Absolute remote path of the directory to copy into
+    pub fn copy_file(&mut self, file: String, destination: String) -> Result<(), IOError> {
+        self.require_ssh("Copying files")?;
+        let mut from = self.dir.path.clone();
+        from.pushd(file.clone());
+        let mut to = Remit::SystemPath::new();
+        to.set_path(destination.clone());
+        to.pushd(file);
+        let is_dir = self.ssh_m.run_command(format!("[ -d \"{}\" ] && echo dir", from.get_path()))? == "dir";
+        self.ssh_m.run_command_checked(format!("cp -rp \"{}\" \"{}\"", from.get_path(), to.get_path()))?;
+        self.invalidate_remote_path(&to.get_path());
+        self.record(Remit::Operation::Create{path: to.get_path(), is_dir: is_dir, trash_id: None});
+        return Ok(());
+    }
+
+    /// Run an operation on several files in the current directory, continuing past failures. Returns the result for each file in
+    /// the order they were given
+    /// # Arguments
+    /// * `files` - Names of the files
+    /// * `operation` - Operation to run on each file
+    pub fn batch(&mut self, files: Vec<String>, operation: &dyn Fn(&mut Manager, String) -> Result<(), IOError>)
+                 -> Vec<(String, Result<(), IOError>)> {
+        let mut results: Vec<(String, Result<(), IOError>)> = Vec::new();
+        for file in files {
+            let r = operation(self, file.clone());
+            results.push((file, r));
+        }
+        return results;
+    }
+
+    /// Delete several files in the current directory. Directories are deleted recursively
+    /// # Arguments
+    /// * `files` - Names of the files to delete
+    pub fn batch_delete(&mut self, files: Vec<String>) -> Vec<(String, Result<(), IOError>)> {
+        return self.batch(files, &|m: &mut Manager, file: String| -> Result<(), IOError> {
+            let recursive = m.dir.files.get(&file).map_or(false, |f| f.info.file_type == Remit::FileType::TypeDirectory);
+            let mut output = String::new();
+            return m.delete_path(file, recursive, &mut output);
+        });
+    }
+
+    /// Change the permissions of a file or directory in the current directory
+    /// # Arguments
+    /// * `file` - Name of the file or directory
+    /// * `mode` - Octal mode e.g. 644 or 0755
+    pub fn chmod_file(&mut self, file: String, mode: String) -> Result<(), IOError> {
+        self.require_ssh("Changing permissions")?;
+        if mode.len() < 3 || mode.len() > 4 || !mode.chars().all(|c| c >= '0' && c <= '7') {
+            return Err(IOError::new(IOErrorKind::InvalidInput, format!("{} is not an octal mode", mode)));
+        }
+        let mut path = self.dir.path.clone();
+        path.pushd(file);
+        let old_mode = self.ssh_m.run_command_checked(format!("stat -c %a \"{}\"", path.get_path()))?;
+        self.ssh_m.run_command_checked(format!("chmod {} \"{}\"", mode, path.get_path()))?;
+        self.invalidate_remote_path(&path.get_path());
+        self.record(Remit::Operation::Chmod{path: path.get_path(), old_mode: old_mode, new_mode: mode});
+        return Ok(());
+    }
+
+    /// Undo the most recent operation of the session. Returns a description of the undone operation
+    /// 
+    /// Created files are moved into the trash, renames are reversed, deleted files are restored from the trash and permissions are
+    /// set back. A delete that wasn't moved into the trash, which only older journals have, can't be undone and is dropped
+    pub fn undo(&mut self) -> Result<String, IOError> {
+        self.require_ssh("Undo")?;
+        let entry = self.journal.peek_undo().ok_or(IOError::new(IOErrorKind::NotFound, "Nothing to undo"))?;
+        let redo_operation;
+        match &entry.operation {
+            Remit::Operation::Create{path, is_dir, ..} => {
+                let mut remote_path = Remit::SystemPath::new();
+                remote_path.set_path(path.clone());
+                let id = self.trash_for_undo(&remote_path)?;
+                self.invalidate_remote_path(path);
+                redo_operation = Remit::Operation::Create{path: path.clone(), is_dir: *is_dir, trash_id: Some(id)};
+            },
+            Remit::Operation::Rename{from, to} => {
+                let mut from_path = Remit::SystemPath::new();
+                let mut to_path = Remit::SystemPath::new();
+                from_path.set_path(from.clone());
+                to_path.set_path(to.clone());
+                self.move_path(&to_path, &from_path)?;
+                redo_operation = entry.operation.clone();
+            },
+            Remit::Operation::Delete{path, trash_id} => {
+                let id = match trash_id {
+                    Some(id) => id.clone(),
+                    None => {
+                        self.journal.discard_undo()?;
+                        return Err(IOError::new(IOErrorKind::Unsupported, format!("{} was deleted without the trash and can't be restored", path)));
+                    }
+                };
+                self.restore_trash(id)?;
+                self.invalidate_remote_path(path);
+                redo_operation = Remit::Operation::Delete{path: path.clone(), trash_id: None};
+            },
+            Remit::Operation::Chmod{path, old_mode, ..} => {
+                self.ssh_m.run_command_checked(format!("chmod {} \"{}\"", old_mode, path))?;
+                self.invalidate_remote_path(path);
+                redo_operation = entry.operation.clone();
+            }
+        }
+        self.journal.mark_undone(redo_operation)?;
+        return Ok(entry.operation.describe());
+    }
+
+    /// Perform the most recently undone operation again. Returns a description of the redone operation
+    pub fn redo(&mut self) -> Result<String, IOError> {
+        self.require_ssh("Redo")?;
+        let entry = self.journal.peek_redo().ok_or(IOError::new(IOErrorKind::NotFound, "Nothing to redo"))?;
+        let undo_operation;
+        match &entry.operation {
+            Remit::Operation::Create{path, is_dir, trash_id} => {
+                match trash_id {
+                    // bring back the content removed by undo
+                    Some(id) => {
+                        self.restore_trash(id.clone())?;
+                    },
+                    None => {
+                        let command = if *is_dir { "mkdir" } else { "touch" };
+                        self.ssh_m.run_command_checked(format!("{} \"{}\"", command, path))?;
+                    }
+                }
+                self.invalidate_remote_path(path);
+                undo_operation = Remit::Operation::Create{path: path.clone(), is_dir: *is_dir, trash_id: None};
+            },
+            Remit::Operation::Rename{from, to} => {
+                let mut from_path = Remit::SystemPath::new();
+                let mut to_path = Remit::SystemPath::new();
+                from_path.set_path(from.clone());
+                to_path.set_path(to.clone());
+                self.move_path(&from_path, &to_path)?;
+                undo_operation = entry.operation.clone();
+            },
+            Remit::Operation::Delete{path, ..} => {
+                let mut remote_path = Remit::SystemPath::new();
+                remote_path.set_path(path.clone());
+                let id = self.trash_for_undo(&remote_path)?;
+                let _r = remove_dir_all(self.local_mirror_path(&remote_path).get_windows_path_local())
+                             .or_else(|_| remove_file(self.local_mirror_path(&remote_path).get_windows_path_local()));
+                self.invalidate_remote_path(path);
+                undo_operation = Remit::Operation::Delete{path: path.clone(), trash_id: Some(id)};
+            },
+            Remit::Operation::Chmod{path, new_mode, ..} => {
+                self.ssh_m.run_command_checked(format!("chmod {} \"{}\"", new_mode, path))?;
+                self.invalidate_remote_path(path);
+                undo_operation = entry.operation.clone();
+            }
+        }
+        self.journal.mark_redone(undo_operation)?;
+        return Ok(entry.operation.describe());
+    }
+
+    /// Get the recorded operations of a session
+    /// # Arguments
+    /// * `session` - Id of the session. If None, the current session is used
+    pub fn history(&self, session: Option<String>) -> Result<Vec<Remit::JournalEntry>, IOError> {
+        match session {
+            Some(session) => return self.journal.load_session(&session),
+            None => return Ok(self.journal.entries())
+        }
+    }
+
+    /// List the ids of every recorded session of the current configuration, oldest first
+    pub fn history_sessions(&self) -> Result<Vec<String>, IOError> {
+        return self.journal.sessions();
+    }
+
+    /// Move a remote path into the trash so the operation removing it can be undone. While the trash is disabled the entry is
+    /// only kept until the session ends, see [`Manager::purge_undo_trash`]. Returns the trash id
+    /// # Arguments
+    /// * `remote_path` - Path to move into the trash
+    fn trash_for_undo(&mut self, remote_path: &Remit::SystemPath) -> Result<String, IOError> {
+        let undo_only = !self.trash_enabled();
+        let id = self.trash_m.trash(&mut self.ssh_m, remote_path, undo_only)?;
+        if undo_only {
+            self.undo_trash.push(id.clone());
+        }
+        return Ok(id);
+    }
+
+    /// Permanently delete the trash entries that were only kept so this session's operations could be undone. Errors are only
+    /// logged since the session is ending. Entries left behind are purged on the next connection
+    pub fn purge_undo_trash(&mut self) {
+        for id in std::mem::take(&mut self.undo_trash) {
+            if let Err(e) = self.trash_m.purge(&mut self.ssh_m, Some(id)) {
+                println!("Error purging trash: {}", e);
+            }
+        }
+    }
+
+    /// Record an operation in the journal. Failing to save the journal doesn't undo the operation so errors are only logged
+    /// # Arguments
+    /// * `operation` - The operation that was performed
+    fn record(&mut self, operation: Remit::Operation) {
+        // undoing needs the trash and shell commands, so operations made without ssh aren't recorded
+        if !self.ssh {
+            return;
+        }
+        if let Err(e) = self.journal.record(operation) {
+            println!("Error saving journal: {}", e);
+        }
+    }
+
+    /// Get the path of a remote file's copy in the local mirror, relative to the current directory
+    /// # Arguments
+    /// * `remote_path` - Absolute remote path
+    fn local_mirror_path(&self, remote_path: &Remit::SystemPath) -> Remit::SystemPath {
+        let mut local_path = Remit::SystemPath::new();
+        local_path.set_win_path(format!("{}\\.remote\\{}", self.current_config_name(), remote_path.get_windows_path_local()));
+        return local_path;
+    }
+
+    /// Rename or move a remote path and its local mirror copy, if there is one
+    /// # Arguments
+    /// * `from` - Absolute remote path to move
+    /// * `to` - Absolute remote path to move to
+    fn move_path(&mut self, from: &Remit::SystemPath, to: &Remit::SystemPath) -> Result<(), IOError> {
+        self.ssh_m.run_command_checked(format!("mv \"{}\" \"{}\"", from.get_path(), to.get_path()))?;
+        let local_to = self.local_mirror_path(to);
+        let mut local_parent = local_to.clone();
+        local_parent.popd();
+        let _r = create_dir_all(local_parent.get_windows_path_local());
+        let _r = rename(self.local_mirror_path(from).get_windows_path_local(), local_to.get_windows_path_local());
+        self.invalidate_remote_path(&from.get_path());
+        self.invalidate_remote_path(&to.get_path());
+        return Ok(());
+    }
+
+    /// Drop cached listings and verified statuses affected by a change to a remote path
+    /// # Arguments
+    /// * `path` - Absolute remote path that changed
+    fn invalidate_remote_path(&mut self, path: &String) {
+        let mut parent = Remit::SystemPath::new();
+        parent.set_path(path.clone());
+        parent.popd();
+        self.listing_cache.invalidate(&parent.get_path());
+        self.listing_cache.invalidate_tree(path);
+        self.forget_sync_status(path);
     }
 
     /// Create a manager using the given configuration parameters
@@ -161,6 +512,37 @@ impl Manager {
         return self.rclone_m.lock().unwrap().rclone_exe_exists();
     }
 
+    /// Get the path of the rclone executable in use, if one was found
+    pub fn rclone_path(&self) -> Option<String> {
+        return self.rclone_m.lock().unwrap().exe_path();
+    }
+
+    /// Get the version of the rclone executable in use, None if it couldn't be run
+    pub fn rclone_version(&self) -> Option<Remit::RCloneVersion> {
+        return self.rclone_m.lock().unwrap().version();
+    }
+
+    /// Check that rclone can be run and is new enough for Remit, see [`crate::syncmanager::rustssh::RCloneManager::check_version`]
+    pub fn check_rclone(&self) -> Result<Remit::RCloneVersion, IOError> {
+        return self.rclone_m.lock().unwrap().check_version();
+    }
+
+    /// Get the rclone features newer than the minimum version that the rclone executable in use supports
+    pub fn rclone_features(&self) -> Vec<Remit::RCloneFeature> {
+        let rclone = self.rclone_m.lock().unwrap();
+        return Remit::RCloneFeature::all().into_iter().filter(|f| rclone.supports(*f)).collect();
+    }
+
+    /// Set and save the path of the rclone executable. The daemon switches to it once no transfers are running
+    /// # Arguments
+    /// * `path` - Path of the rclone executable or of the directory containing it. If None, rclone is looked for next to Remit
+    /// and on the PATH
+    pub fn set_rclone_path(&mut self, path: Option<String>) -> Result<(), IOError> {
+        self.config_m.save_rclone_path(path.as_ref())?;
+        self.rclone_m.lock().unwrap().set_exe(path);
+        return Ok(());
+    }
+
     /// Set the credentials required to connect to a host.
     /// 
     /// The arguments passed into this method has the potential to trigger an rclone configuration creation. If the rclone_config name is not
@@ -175,7 +557,10 @@ impl Manager {
     pub fn set_params(&mut self, host:String, username: String, password: Option<String>, rclone_config: Option<String>,
                         pem_file: Option<String>, port_option: Option<String>) -> Result<(), IOError> {
 
-        // load existing rclone configs by parsing rclone_m config show
+        self.check_rclone()?;
+        // the undo entries belong to the trash of the configuration being replaced
+        self.purge_undo_trash();
+        // load existing rclone configs from rclone config dump
         self.rclone_m.lock().unwrap().load_configs()?;
         let mut full_host = host.clone();
         full_host = format!("{}:{}", full_host, port_option.unwrap_or("22".to_string()));
@@ -192,6 +577,9 @@ impl Manager {
             self.rclone_m.lock().unwrap().set_config(config.clone())?;
             return Ok("".to_string());
         })?;
+        self.ssh = self.rclone_m.lock().unwrap().current_backend() == Some(Remit::Backend::Sftp);
+
+        self.trash_m = Remit::TrashManager::new(self.current_config_name());
 
         // set up our credentials for ssh
         self.ssh_m.set_params(Some(username.clone()), password.clone(), Some(full_host.clone()));
@@ -204,8 +592,28 @@ impl Manager {
     /// and is used to navigate directories. Additionally, it will convert the remote path into a local path (config name/.remote/) to 
     /// save downloaded files. Once converted, it will trigger the start_tracking method to track any modifications to files.
     pub fn connect(&mut self) -> Result<(), IOError>{
-        self.ssh_m.connect()?;
-        if !self.dir.path.set_path(self.ssh_m.run_command("pwd".to_string()).unwrap()) {
+        if self.ssh {
+            self.ssh_m.connect()?;
+        }
+        self.listing_cache.clear();
+        self.journal.start_session(&self.config_m.config_dir(), &self.current_config_name());
+        self.recent = self.config_m.load_history(&self.current_config_name()).unwrap_or_else(|e| {
+            println!("could not load recent history: {}", e);
+            return Remit::RecentHistory::new();
+        });
+        if let Err(e) = self.bisync_m.lock().unwrap().load(&self.config_m.config_dir(), &self.current_config_name()) {
+            println!("could not load sync pairs: {}", e);
+        }
+        if let Some(config) = self.config_m.get_config(&self.current_config_name()) {
+            self.rclone_m.lock().unwrap().set_verification(config.verify_transfers, config.verify_retries);
+            if let Err(e) = self.rclone_m.lock().unwrap().set_limits(config.limits) {
+                println!("could not apply transfer limits: {}", e);
+            }
+            self.rclone_m.lock().unwrap().set_ignore_patterns(config.ignore);
+        }
+        if !self.ssh {
+            self.dir.path.set_path("/".to_string());
+        } else if !self.dir.path.set_path(self.ssh_m.run_command("pwd".to_string()).unwrap()) {
             println!("Error setting path");
         }
         let mut path = Remit::SystemPath::new();
@@ -213,6 +621,20 @@ impl Manager {
         path.pushd(".remote".to_string());
         println!("tracking local changes at: {}", path.get_windows_path());
         self.file_tracker.start_tracking(&mut path)?;
+        if self.ssh {
+            match self.trash_m.purge_undo_only(&mut self.ssh_m) {
+                Ok(removed) => if removed > 0 { println!("purged {} items left from undoing deletions", removed) },
+                Err(e) => println!("could not purge items left from undoing deletions: {}", e)
+            }
+        }
+        if let Some(config) = self.config_m.get_config(&self.current_config_name()) {
+            if config.trash && self.ssh {
+                match self.trash_m.purge_expired(&mut self.ssh_m, config.trash_retention_days) {
+                    Ok(removed) => println!("purged {} expired items from the trash", removed),
+                    Err(e) => println!("could not purge expired items from the trash: {}", e)
+                }
+            }
+        }
         return Ok(());
     }
 
@@ -220,18 +642,299 @@ impl Manager {
     /// TODO add error handling for stop tracking
     pub fn disconnect(&mut self) -> Result<(), IOError> {
         self.file_tracker.stop_tracking();
+        for tracker in self.folder_trackers.values_mut() {
+            tracker.stop_tracking();
+        }
+        self.folder_trackers.clear();
+        self.listing_cache.clear();
+        if !self.ssh {
+            return Ok(());
+        }
+        self.purge_undo_trash();
         return self.ssh_m.disconnect();
     }
 
     /// Load a list of files at the current remote directory
     /// 
-    /// This method only needs to be called when the path has changed. It performs
-    /// and parses an `stat .* *` in the current remote directory.
+    /// This method only needs to be called when the path has changed. If the directory was listed recently, the cached
+    /// listing is used. Otherwise it performs and parses an `stat .* *` in the current remote directory.
     pub fn get_directory(&mut self) -> Result<(), IOError>{
-        self.ssh_m.get_directory(&mut self.dir)?;
+        match self.listing_cache.get(&self.dir.path.get_path()) {
+            Some(files) => {
+                self.dir.files = files;
+                self.recent.push_dir(self.dir.path.get_path());
+                self.save_recent();
+                return Ok(());
+            },
+            None => return self.refresh_directory()
+        }
+    }
+
+    /// Load a list of files at the current remote directory bypassing the listing cache. The new listing
+    /// replaces any cached listing for the path
+    pub fn refresh_directory(&mut self) -> Result<(), IOError>{
+        if self.ssh {
+            self.ssh_m.get_directory(&mut self.dir)?;
+        } else {
+            self.dir.files = self.rclone_m.lock().unwrap().list_remote_dir(&self.dir.path)?;
+        }
+        self.listing_cache.insert(&self.dir);
+        self.recent.push_dir(self.dir.path.get_path());
+        self.save_recent();
+        return Ok(());
+    }
+
+    /// Change the current directory to the most recently visited directory of the current configuration. Meant to be called after
+    /// [`Manager::connect`] to resume where the last session left off. The directory's files are not loaded
+    /// 
+    /// Returns false and stays in the current directory if there is no history or the directory no longer exists
+    pub fn resume_last_directory(&mut self) -> Result<bool, IOError> {
+        let path = match self.recent.last_dir() {
+            Some(path) => path,
+            None => return Ok(false)
+        };
+        if self.check_remote_dir(&path).is_err() {
+            return Ok(false);
+        }
+        self.dir.path.set_path(path);
+        self.dir.files.clear();
+        return Ok(true);
+    }
+
+    /// Get the recently visited directories and opened files of the current configuration, most recent first
+    pub fn recent_history(&self) -> Remit::RecentHistory {
+        return self.recent.clone();
+    }
+
+    /// Clear the recent history of the current configuration
+    pub fn clear_recent_history(&mut self) -> Result<(), IOError> {
+        self.recent.clear();
+        return self.config_m.save_history(&self.current_config_name(), &self.recent);
+    }
+
+    /// Save the recent history. Failing to save it doesn't affect the operation that changed it so errors are only logged
+    fn save_recent(&self) {
+        if let Err(e) = self.config_m.save_history(&self.current_config_name(), &self.recent) {
+            println!("could not save recent history: {}", e);
+        }
+    }
+
+    /// Get the files loaded by [`Manager::get_directory`] filtered and sorted
+    /// # Arguments
+    /// * `options` - Filtering and sorting options. See [`Manager::listing_defaults`] for the current configuration's defaults
+    pub fn list_files(&self, options: &Remit::ListingOptions) -> Vec<Remit::RemitFile> {
+        return self.dir.list(options);
+    }
+
+    /// Get the default listing options of the current configuration. If no configuration is chosen, every file is listed by name
+    pub fn listing_defaults(&self) -> Remit::ListingOptions {
+        return self.config_m.get_config(&self.current_config_name()).map(|c| c.listing)
+                            .unwrap_or(Remit::ListingOptions::new());
+    }
+
+    /// Change the default listing options of the current configuration and save it to disc. Name and type filters are not saved
+    /// # Arguments
+    /// * `options` - New default listing options
+    pub fn set_listing_defaults(&mut self, options: Remit::ListingOptions) -> Result<(), IOError> {
+        return self.update_config(&mut |config: &mut RemitConfig| {
+            config.listing = options.clone();
+            config.listing.name_filter = None;
+            config.listing.type_filter = None;
+        });
+    }
+
+    /// Change the current configuration and save it to disc
+    /// # Arguments
+    /// * `update` - Called with the configuration to change
+    fn update_config(&mut self, update: &mut dyn FnMut(&mut RemitConfig)) -> Result<(), IOError> {
+        let name = self.current_config_name();
+        let mut config = self.config_m.get_config(&name).ok_or(IOError::new(IOErrorKind::NotFound, "Config not found"))?;
+        update(&mut config);
+        self.config_m.insert_config(config);
+        return self.config_m.save_config(name.as_str());
+    }
+
+    /// Check if the current configuration moves deleted files into the trash
+    fn trash_enabled(&self) -> bool {
+        return self.ssh && self.config_m.get_config(&self.current_config_name()).map_or(false, |c| c.trash);
+    }
+
+    /// Change the trash settings of the current configuration and save it to disc
+    /// # Arguments
+    /// * `enabled` - Move deleted files into the trash instead of removing them
+    /// * `retention_days` - Number of days to keep trashed files. 0 keeps them until purged
+    pub fn set_trash_options(&mut self, enabled: bool, retention_days: u64) -> Result<(), IOError> {
+        return self.update_config(&mut |config: &mut RemitConfig| {
+            config.trash = enabled;
+            config.trash_retention_days = retention_days;
+        });
+    }
+
+    /// Change whether transferred files of the current configuration are verified and save it to disc
+    /// # Arguments
+    /// * `enabled` - Check each transferred file against its source
+    /// * `retries` - Number of times a file that doesn't match is copied again before the transfer fails
+    pub fn set_verification_options(&mut self, enabled: bool, retries: u32) -> Result<(), IOError> {
+        self.update_config(&mut |config: &mut RemitConfig| {
+            config.verify_transfers = enabled;
+            config.verify_retries = retries;
+        })?;
+        self.rclone_m.lock().unwrap().set_verification(enabled, retries);
+        return Ok(());
+    }
+
+    /// Change the bandwidth and concurrency limits of the current configuration, apply them to rclone and save them to disc
+    /// # Arguments
+    /// * `limits` - Bandwidth limit or timetable and the number of parallel transfers and checkers
+    pub fn set_transfer_limits(&mut self, limits: Remit::TransferLimits) -> Result<(), IOError> {
+        self.rclone_m.lock().unwrap().set_limits(limits.clone())?;
+        return self.update_config(&mut |config: &mut RemitConfig| {
+            config.limits = limits.clone();
+        });
+    }
+
+    /// Get the ignore patterns of the current configuration
+    pub fn ignore_patterns(&self) -> Vec<String> {
+        return self.config_m.get_config(&self.current_config_name()).map(|c| c.ignore).unwrap_or_default();
+    }
+
+    /// Replace the ignore patterns of the current configuration and save it to disc. Files matching them are no longer uploaded
+    /// by the tracker or copied by directory transfers
+    /// # Arguments
+    /// * `patterns` - gitignore style patterns, one per entry. Empty entries are dropped
+    pub fn set_ignore_patterns(&mut self, patterns: Vec<String>) -> Result<(), IOError> {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.trim().to_string()).filter(|p| p.len() > 0).collect();
+        if patterns.iter().any(|p| p.contains('\n')) {
+            return Err(IOError::new(IOErrorKind::InvalidInput, "Patterns can't contain new lines"));
+        }
+        self.update_config(&mut |config: &mut RemitConfig| {
+            config.ignore = patterns.clone();
+        })?;
+        self.rclone_m.lock().unwrap().set_ignore_patterns(patterns);
+        return Ok(());
+    }
+
+    /// Save a remote directory as a bookmark of the current configuration. An existing bookmark with the same name is replaced
+    /// # Arguments
+    /// * `name` - Name of the bookmark
+    /// * `path` - Absolute remote path of the directory. If None the current directory is used
+    pub fn add_bookmark(&mut self, name: String, path: Option<String>) -> Result<(), IOError> {
+        if name.len() == 0 || name.contains('\t') || name.contains('\n') {
+            return Err(IOError::new(IOErrorKind::InvalidInput, "Invalid bookmark name"));
+        }
+        let path = path.unwrap_or(self.dir.path.get_path());
+        self.check_remote_dir(&path)?;
+        return self.update_config(&mut |config: &mut RemitConfig| {
+            config.bookmarks.insert(name.clone(), path.clone());
+        });
+    }
+
+    /// Remove a bookmark from the current configuration
+    /// # Arguments
+    /// * `name` - Name of the bookmark
+    pub fn remove_bookmark(&mut self, name: String) -> Result<(), IOError> {
+        if !self.list_bookmarks().contains_key(&name) {
+            return Err(IOError::new(IOErrorKind::NotFound, "Bookmark not found"));
+        }
+        return self.update_config(&mut |config: &mut RemitConfig| {
+            config.bookmarks.remove(&name);
+        });
+    }
+
+    /// Get the bookmarks of the current configuration. Paths are stored by bookmark name
+    pub fn list_bookmarks(&self) -> BTreeMap<String, String> {
+        return self.config_m.get_config(&self.current_config_name()).map(|c| c.bookmarks).unwrap_or_default();
+    }
+
+    /// Change the current directory to a bookmarked path after checking it is still a directory on the server. Like
+    /// [`Manager::navigate`], the directory's files are not loaded
+    /// # Arguments
+    /// * `name` - Name of the bookmark
+    pub fn goto_bookmark(&mut self, name: String) -> Result<(), IOError> {
+        let path = self.list_bookmarks().get(&name).cloned().ok_or(IOError::new(IOErrorKind::NotFound, "Bookmark not found"))?;
+        self.check_remote_dir(&path)?;
+        self.dir.path.set_path(path);
+        self.dir.files.clear();
         return Ok(());
     }
 
+    /// Check that a remote path exists and is a directory
+    /// # Arguments
+    /// * `path` - Absolute remote path
+    fn check_remote_dir(&mut self, path: &String) -> Result<(), IOError> {
+        if !self.ssh {
+            let mut remote_path = Remit::SystemPath::new();
+            remote_path.set_path(path.clone());
+            let is_dir = self.rclone_m.lock().unwrap().is_remote_dir(&remote_path).unwrap_or(false);
+            if !is_dir {
+                return Err(IOError::new(IOErrorKind::NotFound, format!("{} is not a directory", path)));
+            }
+            return Ok(());
+        }
+        if self.ssh_m.run_command(format!("[ -d \"{}\" ] && echo dir", path))? != "dir" {
+            return Err(IOError::new(IOErrorKind::NotFound, format!("{} is not a directory", path)));
+        }
+        return Ok(());
+    }
+
+    /// List every item in the remote trash of the current configuration
+    pub fn list_trash(&mut self) -> Result<Vec<Remit::TrashItem>, IOError> {
+        self.require_ssh("The trash")?;
+        return self.trash_m.list(&mut self.ssh_m);
+    }
+
+    /// Restore an item from the remote trash to its original path. The local mirror is not restored, the file can be downloaded again
+    /// # Arguments
+    /// * `id` - Id of the trash entry
+    pub fn restore_trash(&mut self, id: String) -> Result<String, IOError> {
+        self.require_ssh("The trash")?;
+        let path = self.trash_m.restore(&mut self.ssh_m, &id)?;
+        self.undo_trash.retain(|trashed| *trashed != id);
+        let mut parent = Remit::SystemPath::new();
+        parent.set_path(path.clone());
+        parent.popd();
+        self.listing_cache.invalidate(&parent.get_path());
+        return Ok(path);
+    }
+
+    /// Permanently delete an item from the remote trash, or everything in it if no id is given
+    /// # Arguments
+    /// * `id` - Id of the trash entry
+    pub fn purge_trash(&mut self, id: Option<String>) -> Result<(), IOError> {
+        self.require_ssh("The trash")?;
+        return self.trash_m.purge(&mut self.ssh_m, id);
+    }
+
+    /// Return an error if the current configuration has no ssh session
+    /// # Arguments
+    /// * `action` - What needs ssh, used in the error e.g. The trash
+    fn require_ssh(&self, action: &str) -> Result<(), IOError> {
+        if !self.ssh {
+            return Err(IOError::new(IOErrorKind::Unsupported, format!("{} needs an ssh connection", action)));
+        }
+        return Ok(());
+    }
+
+    /// Get the name of the configuration currently in use. Remit and rclone configurations share the same name
+    fn current_config_name(&self) -> String {
+        return self.rclone_m.lock().unwrap().chosen_config.clone();
+    }
+
+    /// Stream the files at the current remote directory in pages without loading the whole listing
+    /// 
+    /// Meant for very large directories. The listing is not stored in the manager's dir object or the listing cache.
+    /// See [`crate::sessionmanager::rustssh::SessionManager::stream_directory`]
+    /// # Arguments
+    /// * `page_size` - Number of files in each page
+    /// * `status` - Set to [`ThreadStatus::Kill`] to cancel the listing
+    /// * `on_page` - Called with each page of files
+    pub fn stream_directory(&mut self, page_size: usize, status: &Arc::<Mutex::<ThreadStatus>>,
+                            on_page: &mut dyn FnMut(Vec<Remit::RemitFile>) -> Result<(), IOError>) -> Result<u64, IOError>{
+        self.require_ssh("Streaming directories")?;
+        let path = self.dir.path.clone();
+        return self.ssh_m.stream_directory(&path, page_size, status, on_page);
+    }
+
     /// adds the name variable to the path after performing a check it exists
     /// and is a directory
     /// 
@@ -246,16 +949,60 @@ impl Manager {
 
     /// Add a RemitConfiguration to the manager. When added to the manager, it will automatically be saved to disc
     /// 
+    /// Only the connection fields of config are used. If a Remit configuration with the same name exists, its other settings like
+    /// bookmarks and the trash options are kept. Nothing is changed if the rclone configuration can't be created
     /// # Arguments
     /// * `config` - Contains the necessary information to save the remit configuration
     /// * `rclone_config` - If the rclone configuration should be different than the remit configuration, pass in a separate configuration
     /// here. Otherwise, the information will be taken from the config parameter
     pub fn add_config(&mut self, config: RemitConfig, rclone_config: Option<RemitConfig>) -> Result<(), IOError>{
-        self.config_m.insert_config(config.clone());
         let rclone_arg = rclone_config.unwrap_or(config.clone());
         self.rclone_m.lock().unwrap().create_sftp_config(rclone_arg.name.clone(), rclone_arg.username.clone() ,rclone_arg.host.clone(), 
                                             Some(rclone_arg.password.clone()), None)?;
-        return self.config_m.save_config(config.name.clone().as_str());
+        let mut saved = self.config_m.get_config(&config.name).unwrap_or(RemitConfig::new());
+        saved.name = config.name.clone();
+        saved.username = config.username;
+        saved.password = config.password;
+        saved.host = config.host;
+        saved.port = config.port;
+        saved.backend = Remit::Backend::Sftp.to_string();
+        self.config_m.insert_config(saved);
+        return self.config_m.save_config(config.name.as_str());
+    }
+
+    /// Create a configuration of any supported rclone backend and save it to disc. Configurations other than sftp are browsed
+    /// through rclone, see [`Manager::open_remote`]
+    /// # Arguments
+    /// * `name` - Name of the Remit and rclone configuration
+    /// * `backend` - Backend of the configuration
+    /// * `options` - Values of the backend's options by name, see [`crate::syncmanager::rustssh::Backend::options`]
+    pub fn add_remote_config(&mut self, name: String, backend: Remit::Backend, options: BTreeMap<String, String>) -> Result<(), IOError>{
+        if self.config_m.get_config(&name).is_some() {
+            return Err(IOError::new(IOErrorKind::AlreadyExists, "Config already exists"));
+        }
+        let mut config = RemitConfig::new();
+        config.name = name.clone();
+        config.backend = backend.to_string();
+        config.username = options.get("user").cloned().unwrap_or_default();
+        config.port = options.get("port").cloned().unwrap_or_default();
+        config.host = ["host", "url", "endpoint"].iter().find_map(|o| options.get(*o).cloned()).unwrap_or_default();
+        self.rclone_m.lock().unwrap().create_config(name.clone(), backend, options)?;
+        self.config_m.insert_config(config);
+        return self.config_m.save_config(name.as_str());
+    }
+
+    /// Choose a configuration to browse through rclone without an ssh session. Works for every backend, including sftp
+    /// configurations when only file access is needed. Call [`Manager::connect`] afterwards
+    /// # Arguments
+    /// * `config` - Name of the rclone configuration
+    pub fn open_remote(&mut self, config: String) -> Result<(), IOError>{
+        self.check_rclone()?;
+        self.purge_undo_trash();
+        self.rclone_m.lock().unwrap().load_configs()?;
+        self.rclone_m.lock().unwrap().set_config(config)?;
+        self.trash_m = Remit::TrashManager::new(self.current_config_name());
+        self.ssh = false;
+        return Ok(());
     }
 
     /// Downloads a remote file to the current disc. If the open flag is Some(true), attempt to open the file
@@ -270,9 +1017,14 @@ impl Manager {
         local_path.set_win_path(format!("{}\\.remote\\{}", self.rclone_m.lock().unwrap().chosen_config.clone(), self.dir.path.get_windows_path_local()));
         let mut remote_path = Remit::SystemPath::new();
         remote_path.set_win_path(self.dir.path.get_path());
-        let r = self.rclone_m.lock().unwrap().download_remote_file(local_path.clone(), remote_path, name.clone()).unwrap();
+        let r = self.rclone_m.lock().unwrap().download_remote_file(local_path.clone(), remote_path, name.clone())?;
         // on a success, if open is set then open the file using windows explorer ( allows a chance to set the default application)
         if r.success() {
+            let mut synced_path = self.dir.path.clone();
+            synced_path.pushd(name.clone());
+            self.forget_sync_status(&synced_path.get_path());
+            self.recent.push_file(synced_path.get_path());
+            self.save_recent();
             open.map(|open: bool| {
                 if open {
                     local_path.pushd(name);
@@ -287,6 +1039,189 @@ impl Manager {
         }
     }
 
+    /// Preview the start of a file in the current remote directory without downloading it to the local mirror
+    /// 
+    /// Only the first max_kb kilobytes are read over sftp. Images are read whole if they are smaller than 2 MB so they can be displayed.
+    /// See [`crate::filepreview::rustssh::FilePreview`] for how the content is detected
+    /// # Arguments
+    /// * `name` - Name of the file to preview
+    /// * `max_kb` - Number of kilobytes to read. If None assume 64
+    pub fn preview_file(&mut self, name: String, max_kb: Option<u64>) -> Result<Remit::FilePreview, IOError>{
+        self.require_ssh("Previewing files")?;
+        let mut remote_path = self.dir.path.clone();
+        remote_path.pushd(name);
+        let (bytes, size) = self.ssh_m.read_remote_bytes(&remote_path.get_path(), max_kb.unwrap_or(64).saturating_mul(1024))?;
+        let preview = Remit::FilePreview::new(&bytes, size);
+        if preview.kind == Remit::PreviewKind::Image && preview.truncated && size <= 2 * 1024 * 1024 {
+            let (image, size) = self.ssh_m.read_remote_bytes(&remote_path.get_path(), size)?;
+            return Ok(Remit::FilePreview::new(&image, size));
+        }
+        return Ok(preview);
+    }
+
+    /// Compare a file or directory in the current remote directory with its copy in the local mirror
+    /// 
+    /// Hashes are computed on the server with sha256sum, or md5sum if it isn't available, and locally through rclone with the
+    /// same algorithm. Directories are compared recursively. The status of each file is returned by its remote path and remembered
+    /// so it can be shown in listings. A directory is reported as matching only if every file inside matches
+    /// # Arguments
+    /// * `name` - Name of the file or directory to verify
+    pub fn verify(&mut self, name: String) -> Result<BTreeMap<String, Remit::SyncStatus>, IOError>{
+        self.require_ssh("Verifying files")?;
+        let is_dir = self.dir.files.get(&name).map_or(false, |f| f.info.file_type == Remit::FileType::TypeDirectory);
+        let mut remote_path = self.dir.path.clone();
+        remote_path.pushd(name.clone());
+        let mut local_path = Remit::SystemPath::new();
+        local_path.set_win_path(format!("{}\\.remote\\{}", self.current_config_name(), self.dir.path.get_windows_path_local()));
+        local_path.pushd(name.clone());
+
+        let algorithm = self.ssh_m.run_command("command -v sha256sum >/dev/null && echo sha256 || echo md5".to_string())?;
+        let remote_output;
+        if is_dir {
+            remote_output = self.ssh_m.run_command(format!("cd \"{}\" && find . -type f -exec {}sum {{}} +", remote_path.get_path(), algorithm))?;
+        } else {
+            remote_output = self.ssh_m.run_command(format!("{}sum \"{}\"", algorithm, remote_path.get_path()))?;
+        }
+        let mut local_output = String::new();
+        if metadata(local_path.get_windows_path_local()).is_ok() {
+            local_output = self.rclone_m.lock().unwrap().hashsum(&algorithm, &local_path)?;
+        }
+
+        let mut remote_hashes = parse_hashes(&remote_output);
+        let mut local_hashes = parse_hashes(&local_output);
+        if !is_dir {
+            // a single file is reported under a different path by each command so match it by name
+            remote_hashes = remote_hashes.into_values().take(1).map(|hash| (name.clone(), hash)).collect();
+            local_hashes = local_hashes.into_values().take(1).map(|hash| (name.clone(), hash)).collect();
+        }
+
+        let mut statuses: BTreeMap<String, Remit::SyncStatus> = BTreeMap::new();
+        for (relative, status) in compare(&local_hashes, &remote_hashes) {
+            let mut path = remote_path.clone();
+            if is_dir {
+                for part in relative.split('/') {
+                    path.pushd(part.to_string());
+                }
+            }
+            statuses.insert(path.get_path(), status);
+        }
+        if is_dir {
+            let mut dir_status = Remit::SyncStatus::Match;
+            if statuses.values().any(|s| *s != Remit::SyncStatus::Match) {
+                dir_status = Remit::SyncStatus::Differ;
+            }
+            statuses.insert(remote_path.get_path(), dir_status);
+        }
+        for (path, status) in &statuses {
+            self.sync_status.insert(path.clone(), status.clone());
+        }
+        return Ok(statuses);
+    }
+
+    /// Produce a unified diff between the current remote content of a file and its copy in the local mirror
+    /// 
+    /// Lines starting with `-` are only on the server and lines starting with `+` are only in the local copy, so the diff shows
+    /// what an upload would change. Files larger than 5 MB are refused
+    /// # Arguments
+    /// * `name` - Name of a file in the current remote directory
+    /// * `context` - Number of unchanged lines shown around each change. If None assume 3
+    pub fn diff_file(&mut self, name: String, context: Option<usize>) -> Result<Remit::FileDiff, IOError>{
+        self.require_ssh("Comparing files")?;
+        let max_bytes = 5 * 1024 * 1024;
+        let mut remote_path = self.dir.path.clone();
+        remote_path.pushd(name.clone());
+        let mut local_path = Remit::SystemPath::new();
+        local_path.set_win_path(format!("{}\\.remote\\{}", self.current_config_name(), self.dir.path.get_windows_path_local()));
+        local_path.pushd(name.clone());
+
+        let (remote_bytes, size) = self.ssh_m.read_remote_bytes(&remote_path.get_path(), max_bytes)?;
+        if size > max_bytes {
+            return Err(IOError::new(IOErrorKind::InvalidInput, "File is too large to diff"));
+        }
+        let local_bytes = read(local_path.get_windows_path_local())?;
+        if local_bytes.len() as u64 > max_bytes {
+            return Err(IOError::new(IOErrorKind::InvalidInput, "File is too large to diff"));
+        }
+
+        let identical = remote_bytes == local_bytes;
+        if is_binary(&remote_bytes) || is_binary(&local_bytes) {
+            return Ok(Remit::FileDiff{binary: true, identical: identical, unified: String::new()});
+        }
+        let (remote_text, _) = decode_text(&remote_bytes).unwrap_or_default();
+        let (local_text, _) = decode_text(&local_bytes).unwrap_or_default();
+        let unified = unified_diff(&remote_text, &local_text, &format!("remote:{}", remote_path.get_path()),
+                                   &format!("local:{}", local_path.get_path()), context.unwrap_or(3));
+        return Ok(Remit::FileDiff{binary: false, identical: identical, unified: unified});
+    }
+
+    /// Get the last verified status of a file in the current remote directory. Returns None if the file hasn't been verified
+    /// since it was last changed by Remit
+    /// # Arguments
+    /// * `name` - Name of the file
+    pub fn sync_status(&self, name: &String) -> Option<Remit::SyncStatus> {
+        let mut path = self.dir.path.clone();
+        path.pushd(name.clone());
+        return self.sync_status.get(&path.get_path()).cloned();
+    }
+
+    /// Forget the verified status of a remote path and everything below it
+    /// # Arguments
+    /// * `path` - Remote path
+    fn forget_sync_status(&mut self, path: &String) {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        self.sync_status.retain(|key, _| key != path && !key.starts_with(&prefix));
+    }
+
+    /// Read a small text file in the current remote directory directly over sftp, without going through the local mirror.
+    /// Returns the text, the file's version and its encoding, which must be passed back to [`Manager::write_remote_file`].
+    /// Files that can't be decoded exactly are refused so saving them can't change their encoding
+    /// # Arguments
+    /// * `name` - Name of the file to read
+    /// * `max_bytes` - Largest file accepted. If None assume 1 MB
+    pub fn read_remote_file(&mut self, name: String, max_bytes: Option<u64>) -> Result<(String, String, String), IOError>{
+        self.require_ssh("Editing remote files")?;
+        let mut remote_path = self.dir.path.clone();
+        remote_path.pushd(name);
+        let version = self.ssh_m.remote_version(&remote_path.get_path())?;
+        let (bytes, size) = self.ssh_m.read_remote_bytes(&remote_path.get_path(), max_bytes.unwrap_or(1024 * 1024))?;
+        if (bytes.len() as u64) < size {
+            return Err(IOError::new(IOErrorKind::InvalidData, format!("{} is too large to edit directly", remote_path.get_path())));
+        }
+        let (text, encoding) = decode_exact(&bytes).ok_or(IOError::new(IOErrorKind::InvalidData,
+                                                                        format!("{} is not a text file", remote_path.get_path())))?;
+        return Ok((text, version, encoding.to_string()));
+    }
+
+    /// Write new contents to a file in the current remote directory directly over sftp. The file is replaced atomically and the
+    /// new version is returned
+    /// 
+    /// If a version is given and the file has changed on the server since it was read, nothing is written and an error starting
+    /// with `Conflict` is returned
+    /// # Arguments
+    /// * `name` - Name of the file to write
+    /// * `content` - New contents
+    /// * `expected_version` - Version returned by [`Manager::read_remote_file`]. If None the file is overwritten unconditionally
+    /// * `encoding` - Encoding returned by [`Manager::read_remote_file`]. If None the contents are written as UTF-8
+    pub fn write_remote_file(&mut self, name: String, content: String, expected_version: Option<String>,
+                             encoding: Option<String>) -> Result<String, IOError>{
+        self.require_ssh("Editing remote files")?;
+        let mut remote_path = self.dir.path.clone();
+        remote_path.pushd(name);
+        let encoding = encoding.unwrap_or("utf-8".to_string());
+        let bytes = encode_text(&content, &encoding).ok_or(IOError::new(IOErrorKind::InvalidInput,
+                                                                       format!("The contents can't be written as {}", encoding)))?;
+        if let Some(expected) = expected_version {
+            let current = self.ssh_m.remote_version(&remote_path.get_path())?;
+            if current != expected {
+                return Err(IOError::new(IOErrorKind::Other, format!("Conflict: {} was changed on the server since it was read",
+                                                                    remote_path.get_path())));
+            }
+        }
+        self.ssh_m.write_remote_bytes(&remote_path, &bytes)?;
+        self.invalidate_remote_path(&remote_path.get_path());
+        return self.ssh_m.remote_version(&remote_path.get_path());
+    }
+
     /// Use rclone to upload a local file
     /// 
     /// This method uses the file name and the manager directory object to construct a file path on the local and remote system.
@@ -302,6 +1237,239 @@ impl Manager {
         }
     }
 
+    /// Start the rclone daemon so transfers can run as async jobs. See [`Manager::download_file_async`]
+    pub fn start_daemon(&mut self) -> Result<(), IOError>{
+        return self.rclone_m.lock().unwrap().start_daemon();
+    }
+
+    /// Shut the rclone daemon down. Must be called before the app exits
+    pub fn stop_daemon(&mut self) {
+        self.rclone_m.lock().unwrap().stop_daemon();
+    }
+
+    /// Set the listener that receives the progress and result of every transfer started through the rclone daemon
+    /// # Arguments
+    /// * `listener` - Called from a separate thread for each transfer
+    pub fn set_transfer_listener(&mut self, listener: Remit::TransferListener) {
+        self.transfer_queue.lock().unwrap().set_listener(listener.clone());
+        self.transfer_listener = Some(listener);
+    }
+
+    /// Get the transfer queue. Lock it only for short operations, its thread needs it to follow running transfers
+    pub fn transfer_queue(&self) -> Arc::<Mutex::<Remit::TransferQueue>> {
+        return self.transfer_queue.clone();
+    }
+
+    /// Get the two way sync manager of the current configuration. Lock it only for short operations, use
+    /// [`crate::bisyncmanager::rustssh::BisyncManager::run`] to run a pair
+    pub fn bisync_manager(&self) -> Arc::<Mutex::<Remit::BisyncManager>> {
+        return self.bisync_m.clone();
+    }
+
+    /// Keep a remote directory and a local directory in sync both ways. Returns the id of the new sync pair
+    /// # Arguments
+    /// * `remote_path` - Absolute remote path of the directory. If None the current directory is used
+    /// * `local_path` - Local directory relative to the current directory. If None the remote directory's copy in the local mirror is used
+    /// * `interval` - Minutes between scheduled runs. 0 only runs on demand
+    pub fn add_sync_pair(&mut self, remote_path: Option<String>, local_path: Option<String>, interval: u64) -> Result<u64, IOError>{
+        let remote_path = remote_path.unwrap_or(self.dir.path.get_path());
+        self.check_remote_dir(&remote_path)?;
+        let local_path = match local_path {
+            Some(path) => {
+                let mut local = Remit::SystemPath::new();
+                local.set_win_path(path);
+                local
+            },
+            None => {
+                let mut remote = Remit::SystemPath::new();
+                remote.set_path(remote_path.clone());
+                self.local_mirror_path(&remote)
+            }
+        };
+        return self.bisync_m.lock().unwrap().add_pair(remote_path, local_path, interval);
+    }
+
+    /// Queue a download of a file in the current remote directory to the local mirror. Returns the transfer's queue id
+    /// # Arguments
+    /// * `name` - Name of the file to download
+    /// * `priority` - Higher priorities start first. If None assume 0
+    pub fn queue_download(&mut self, name: String, priority: Option<i32>) -> Result<u64, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        let (local_fs, remote_fs) = {
+            let rclone = self.rclone_m.lock().unwrap();
+            (rclone.local_fs(&local_path), rclone.remote_fs(&self.dir.path))
+        };
+        let id = self.transfer_queue.lock().unwrap().add(Remit::TransferKind::Download, remote_fs, local_fs, name.clone(), priority.unwrap_or(0));
+        let mut synced_path = self.dir.path.clone();
+        synced_path.pushd(name);
+        self.forget_sync_status(&synced_path.get_path());
+        self.recent.push_file(synced_path.get_path());
+        self.save_recent();
+        return Ok(id);
+    }
+
+    /// Queue an upload of a file from the local mirror to the current remote directory. Returns the transfer's queue id
+    /// # Arguments
+    /// * `name` - Name of the file to upload
+    /// * `priority` - Higher priorities start first. If None assume 0
+    pub fn queue_upload(&mut self, name: String, priority: Option<i32>) -> Result<u64, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        let (local_fs, remote_fs) = {
+            let rclone = self.rclone_m.lock().unwrap();
+            (rclone.local_fs(&local_path), rclone.remote_fs(&self.dir.path))
+        };
+        return Ok(self.transfer_queue.lock().unwrap().add(Remit::TransferKind::Upload, local_fs, remote_fs, name, priority.unwrap_or(0)));
+    }
+
+    /// Report the progress of a daemon job to the transfer listener, if there is one
+    /// # Arguments
+    /// * `id` - Job id
+    fn watch_transfer(&self, id: u64) {
+        if let Some(listener) = &self.transfer_listener {
+            Remit::RCloneManager::watch_job(&self.rclone_m, id, listener.clone());
+        }
+    }
+
+    /// Start downloading a file in the current remote directory to the local mirror through the rclone daemon. Returns the
+    /// job id straight away. Progress is sent to the transfer listener, the job can also be polled with [`Manager::job_status`]
+    /// # Arguments
+    /// * `name` - Name of the file to download
+    pub fn download_file_async(&mut self, name: String) -> Result<u64, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        let id = self.rclone_m.lock().unwrap().start_download_job(local_path, self.dir.path.clone(), name.clone())?;
+        let mut synced_path = self.dir.path.clone();
+        synced_path.pushd(name);
+        self.forget_sync_status(&synced_path.get_path());
+        self.recent.push_file(synced_path.get_path());
+        self.save_recent();
+        self.watch_transfer(id);
+        return Ok(id);
+    }
+
+    /// Start uploading a file from the local mirror to the current remote directory through the rclone daemon. Returns the job id
+    /// # Arguments
+    /// * `name` - Name of the file to upload
+    pub fn upload_file_async(&mut self, name: String) -> Result<u64, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        let id = self.rclone_m.lock().unwrap().start_upload_job(local_path, self.dir.path.clone(), name)?;
+        self.watch_transfer(id);
+        return Ok(id);
+    }
+
+    /// Download a whole remote directory through the rclone daemon and return the job id. Progress is sent to the transfer listener
+    /// 
+    /// By default the directory is copied into its place in the local mirror, which is already tracked. Changes under the directory
+    /// are skipped by the mirror's tracker until the download has finished. A directory downloaded into another folder gets its own
+    /// tracker so changes made there are uploaded back to the directory too. The tracker only starts once the download has succeeded.
+    /// Either way the downloaded files aren't uploaded again. Tracking stops on disconnect
+    /// # Arguments
+    /// * `name` - Name of a directory in the current remote directory. If None the current directory is downloaded
+    /// * `local_path` - Folder to download into, relative to the current directory. If None the local mirror is used
+    /// * `filter` - Depth and patterns limiting which files are downloaded
+    pub fn download_directory(&mut self, name: Option<String>, local_path: Option<String>, filter: Remit::DirectoryFilter) -> Result<u64, IOError>{
+        let (remote_path, local_dir) = self.directory_download_paths(name, &local_path)?;
+        let id = {
+            let mut rclone = self.rclone_m.lock().unwrap();
+            rclone.start_daemon()?;
+            rclone.start_directory_download_job(&local_dir, &remote_path, &filter)?
+        };
+        self.watch_transfer(id);
+        if local_path.is_none() {
+            self.file_tracker.skip_during_job(remote_path.get_path(), id);
+            self.forget_sync_status(&remote_path.get_path());
+        } else {
+            let key = local_dir.get_windows_path_local();
+            if let Some(mut old) = self.folder_trackers.remove(&key) {
+                old.stop_tracking();
+            }
+            let mut tracker = Remit::DirectoryTracker::new(local_dir.clone(), self.rclone_m.clone());
+            tracker.set_remote_root(remote_path.get_path());
+            let mut tracked_path = local_dir.clone();
+            tracker.start_tracking_after_job(&mut tracked_path, id)?;
+            self.folder_trackers.insert(key, tracker);
+        }
+        return Ok(id);
+    }
+
+    /// Get the changes [`Manager::download_directory`] would make to the local folder without downloading anything
+    /// # Arguments
+    /// * `name` - Name of a directory in the current remote directory. If None the current directory is used
+    /// * `local_path` - Folder to download into, relative to the current directory. If None the local mirror is used
+    /// * `filter` - Depth and patterns limiting which files are downloaded
+    pub fn plan_directory_download(&mut self, name: Option<String>, local_path: Option<String>, filter: Remit::DirectoryFilter) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let (remote_path, local_dir) = self.directory_download_paths(name, &local_path)?;
+        return self.rclone_m.lock().unwrap().plan_directory_download(&local_dir, &remote_path, &filter);
+    }
+
+    /// Get the remote directory and local folder of a directory download after checking the remote directory exists
+    /// # Arguments
+    /// * `name` - Name of a directory in the current remote directory. If None the current directory is used
+    /// * `local_path` - Folder to download into. If None the remote directory's copy in the local mirror is used
+    fn directory_download_paths(&mut self, name: Option<String>, local_path: &Option<String>) -> Result<(Remit::SystemPath, Remit::SystemPath), IOError>{
+        let mut remote_path = self.dir.path.clone();
+        if let Some(name) = name {
+            remote_path.pushd(name);
+        }
+        self.check_remote_dir(&remote_path.get_path())?;
+        let local_dir = match local_path {
+            Some(path) => {
+                let mut local = Remit::SystemPath::new();
+                local.set_win_path(path.clone());
+                local
+            },
+            None => self.local_mirror_path(&remote_path)
+        };
+        return Ok((remote_path, local_dir));
+    }
+
+    /// Get the status of a transfer job started through the rclone daemon
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn job_status(&mut self, id: u64) -> Result<Remit::JobStatus, IOError>{
+        return self.rclone_m.lock().unwrap().job_status(id);
+    }
+
+    /// Cancel a transfer job started through the rclone daemon
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn stop_job(&mut self, id: u64) -> Result<(), IOError>{
+        return self.rclone_m.lock().unwrap().stop_job(id);
+    }
+
+    /// Get the changes [`Manager::download_file`] would make to the local mirror without downloading anything
+    /// # Arguments
+    /// * `name` - Name of a file in the current remote directory
+    pub fn plan_download(&mut self, name: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        return self.rclone_m.lock().unwrap().plan_download(local_path, self.dir.path.clone(), name);
+    }
+
+    /// Get the changes uploading a file from the local mirror to the current remote directory would make on the server, without
+    /// uploading anything
+    /// # Arguments
+    /// * `name` - Name of a file in the current directory's local mirror
+    pub fn plan_upload(&mut self, name: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        return self.rclone_m.lock().unwrap().plan_upload(local_path, self.dir.path.clone(), name);
+    }
+
+    /// Get the changes [`Manager::download_file_async`] or [`Manager::queue_download`] would make to the local mirror without
+    /// downloading anything
+    /// # Arguments
+    /// * `name` - Name of a file in the current remote directory
+    pub fn plan_download_async(&mut self, name: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        return self.rclone_m.lock().unwrap().plan_download_job(local_path, self.dir.path.clone(), name);
+    }
+
+    /// Get the changes [`Manager::upload_file_async`] or [`Manager::queue_upload`] would make on the server without uploading anything
+    /// # Arguments
+    /// * `name` - Name of a file in the current directory's local mirror
+    pub fn plan_upload_async(&mut self, name: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let local_path = self.local_mirror_path(&self.dir.path);
+        return self.rclone_m.lock().unwrap().plan_upload_job(local_path, self.dir.path.clone(), name);
+    }
+
     /// Load a list of remit configurations
     pub fn get_configs(&mut self) -> Vec<Remit::Config>{
         return self.config_m.get_configs();
@@ -0,0 +1,389 @@
+//! A long lived `rclone rcd` process controlled through rclone's remote control (rc) API. Starting rclone once and sending it
+//! commands avoids spawning a process for every transfer and lets rclone reuse its connections to the server.
+//!
+//! The daemon only listens on 127.0.0.1 on a free port and requires basic auth with a user and a random password generated each
+//! time it is started. Both are passed to rclone in its environment so they don't show up in the process list. Commands are sent
+//! as `POST /<command>` with a JSON body and return a JSON object. Transfers are started with `_async` so they return a job id
+//! straight away, the job can then be polled with `job/status` and cancelled with `job/stop`.
+//! See <https://rclone.org/rc/> for the commands.
+//!
+//! Every job has its own stats group, so the progress of a single transfer can be read from `core/stats` while it runs. A
+//! [`TransferListener`] receives that progress as [`TransferEvent`]s, see `RCloneManager::watch_job`.
+
+pub mod rustssh {
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::ops::Deref;
+use std::sync::Arc;
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::*;
+use crate::filepreview::rustssh::base64_encode;
+use crate::syncmanager::rustssh::hide_window;
+
+/// User name the daemon is started with
+static RC_USER: &str = "remit";
+
+/// Status of an async rc job as returned by `job/status`
+#[derive(Clone, Debug, Deserialize)]
+pub struct JobStatus {
+    /// Job id
+    pub id: u64,
+    pub finished: bool,
+    /// Only meaningful once the job is finished
+    pub success: bool,
+    /// Error text of a failed job. Empty if the job succeeded or is still running
+    #[serde(default)]
+    pub error: String,
+    /// Seconds the job has been running
+    #[serde(default)]
+    pub duration: f64
+}
+
+/// Transfer statistics of a stats group as returned by `core/stats`
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransferStats {
+    /// Bytes transferred so far
+    #[serde(default)]
+    pub bytes: u64,
+    /// Total bytes to transfer. 0 until rclone knows the size
+    #[serde(default, rename = "totalBytes")]
+    pub total_bytes: u64,
+    /// Bytes per second
+    #[serde(default)]
+    pub speed: f64,
+    /// Estimated seconds left. None if unknown
+    #[serde(default)]
+    pub eta: Option<f64>
+}
+
+/// What the id of a transfer event refers to. Daemon jobs and queued transfers are numbered separately
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferSource {
+    /// A job started directly on the daemon. The id is the daemon's job id
+    Job,
+    /// A transfer of the `TransferQueue`. The id is the queue's transfer id
+    Queue
+}
+
+/// Progress of a running transfer sent to a [`TransferListener`]
+#[derive(Clone, Debug, Serialize)]
+pub struct TransferProgress {
+    /// Job id or transfer queue id of the transfer, see source
+    pub id: u64,
+    pub source: TransferSource,
+    pub bytes: u64,
+    pub total_bytes: u64,
+    /// Bytes per second
+    pub speed: f64,
+    /// Estimated seconds left. None if unknown
+    pub eta: Option<f64>
+}
+
+/// Outcome of a finished transfer sent to a [`TransferListener`]
+#[derive(Clone, Debug, Serialize)]
+pub struct TransferResult {
+    /// Job id or transfer queue id of the transfer, see source
+    pub id: u64,
+    pub source: TransferSource,
+    pub success: bool,
+    /// Error text of a failed transfer. Empty on success
+    pub error: String
+}
+
+/// Events reported while watching a transfer. A transfer sends any number of Progress events followed by one Finished event
+#[derive(Clone, Debug)]
+pub enum TransferEvent {
+    Progress(TransferProgress),
+    Finished(TransferResult)
+}
+
+/// Callback receiving the events of watched transfers. Called from the thread watching the transfer
+pub type TransferListener = Arc<dyn Fn(TransferEvent) + Send + Sync>;
+
+/// The address and credentials of a running daemon. Cheap to clone, so commands can be sent without holding the rclone manager
+#[derive(Clone, Debug)]
+pub struct RcClient {
+    /// Port the daemon listens on at 127.0.0.1
+    port: u16,
+    /// Value of the Authorization header
+    auth: String
+}
+
+/// A running `rclone rcd` process and the client to talk to it. Derefs to the [`RcClient`]
+pub struct RCloneDaemon {
+    process: Child,
+    client: RcClient
+}
+
+impl Deref for RCloneDaemon {
+    type Target = RcClient;
+
+    fn deref(&self) -> &RcClient {
+        return &self.client;
+    }
+}
+
+#[allow(dead_code)]
+impl RCloneDaemon {
+    /// Start `rclone rcd` on a free localhost port and wait until it answers. Errors if rclone exits or doesn't answer within
+    /// 10 seconds
+    /// # Arguments
+    /// * `exe` - Path to the rclone executable
+    /// * `args` - Extra flags for rclone e.g. --bwlimit
+    pub fn start(exe: &PathBuf, args: &Vec<String>) -> Result<RCloneDaemon, IOError> {
+        // bind port 0 to have the os pick a free port, then release it for rclone
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let password = RCloneDaemon::random_password()?;
+        let process = hide_window(&mut Command::new(exe))
+                    .arg("rcd")
+                    .arg(format!("--rc-addr=127.0.0.1:{}", port))
+                    // passed in the environment, which unlike the command line other users can't read
+                    .env("RCLONE_RC_USER", RC_USER)
+                    .env("RCLONE_RC_PASS", &password)
+                    .args(args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+        let auth = format!("Basic {}", base64_encode(format!("{}:{}", RC_USER, password).as_bytes()));
+        let mut daemon = RCloneDaemon{process: process, client: RcClient{port: port, auth: auth}};
+
+        let started = Instant::now();
+        loop {
+            if let Some(status) = daemon.process.try_wait()? {
+                return Err(IOError::new(IOErrorKind::Other, format!("rclone rcd exited with {}", status)));
+            }
+            if daemon.call("rc/noop", &json!({})).is_ok() {
+                return Ok(daemon);
+            }
+            if started.elapsed() > Duration::from_secs(10) {
+                daemon.kill();
+                return Err(IOError::new(IOErrorKind::TimedOut, "rclone rcd did not start"));
+            }
+            sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Generate a 128 bit password for the daemon from the operating system's secure random number generator
+    fn random_password() -> Result<String, IOError> {
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes).map_err(|e| IOError::new(IOErrorKind::Other, format!("Could not generate a password: {}", e)))?;
+        return Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect());
+    }
+
+    /// Check that the daemon process is still running
+    pub fn is_running(&mut self) -> bool {
+        return matches!(self.process.try_wait(), Ok(None));
+    }
+
+    /// Get a copy of the client to send commands without borrowing the daemon
+    pub fn client(&self) -> RcClient {
+        return self.client.clone();
+    }
+
+    /// Ask the daemon to quit and wait up to 5 seconds for it to exit before killing it
+    pub fn stop(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        let _r = self.call("core/quit", &json!({}));
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_secs(5) {
+            if !self.is_running() {
+                return;
+            }
+            sleep(Duration::from_millis(100));
+        }
+        self.kill();
+    }
+
+    /// Kill the daemon process
+    fn kill(&mut self) {
+        let _r = self.process.kill();
+        let _r = self.process.wait();
+    }
+}
+
+impl Drop for RCloneDaemon {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+
+#[allow(dead_code)]
+impl RcClient {
+    /// Send a command to the daemon and return its JSON response. Errors with the daemon's error message if the command fails
+    /// # Arguments
+    /// * `command` - rc command e.g. operations/copyfile
+    /// * `params` - JSON object of parameters
+    pub fn call(&self, command: &str, params: &Value) -> Result<Value, IOError> {
+        let body = params.to_string();
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port))?;
+        stream.set_read_timeout(Some(Duration::from_secs(60)))?;
+        // HTTP/1.0 so the response isn't chunked and ends when the daemon closes the connection
+        let request = format!("POST /{} HTTP/1.0\r\nHost: 127.0.0.1:{}\r\nAuthorization: {}\r\nContent-Type: application/json\r\n\
+                               Content-Length: {}\r\n\r\n{}", command, self.port, self.auth, body.len(), body);
+        stream.write_all(request.as_bytes())?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        let response = String::from_utf8_lossy(&response).to_string();
+
+        let (head, content) = response.split_once("\r\n\r\n").ok_or(IOError::new(IOErrorKind::InvalidData, "Invalid rc response"))?;
+        let status = head.split_whitespace().nth(1).and_then(|s| s.parse::<u16>().ok())
+                        .ok_or(IOError::new(IOErrorKind::InvalidData, "Invalid rc response status"))?;
+        let value: Value = serde_json::from_str(content).unwrap_or(Value::Null);
+        if status != 200 {
+            let error = value.get("error").and_then(|e| e.as_str()).map(|e| e.to_string()).unwrap_or(content.to_string());
+            let kind = if status == 401 || status == 403 { IOErrorKind::PermissionDenied } else { IOErrorKind::Other };
+            return Err(IOError::new(kind, format!("{} failed: {}", command, error)));
+        }
+        return Ok(value);
+    }
+
+    /// Start an async job and return its id
+    /// # Arguments
+    /// * `command` - rc command
+    /// * `params` - JSON object of parameters. `_async` is added
+    pub fn start_job(&self, command: &str, mut params: Value) -> Result<u64, IOError> {
+        params["_async"] = json!(true);
+        let response = self.call(command, &params)?;
+        return response.get("jobid").and_then(|id| id.as_u64())
+                        .ok_or(IOError::new(IOErrorKind::InvalidData, format!("{} did not return a job id", command)));
+    }
+
+    /// Copy one file between two remotes as a job. Local paths can be used as a remote
+    /// # Arguments
+    /// * `src_fs` - Remote containing the file e.g. config:/home/user
+    /// * `src_remote` - Path of the file in src_fs
+    /// * `dst_fs` - Remote to copy to
+    /// * `dst_remote` - Path of the copy in dst_fs
+    pub fn copy_file(&self, src_fs: &String, src_remote: &String, dst_fs: &String, dst_remote: &String) -> Result<u64, IOError> {
+        return self.start_job("operations/copyfile", json!({"srcFs": src_fs, "srcRemote": src_remote,
+                                                               "dstFs": dst_fs, "dstRemote": dst_remote}));
+    }
+
+    /// Copy every file of a directory that passes a filter as a job. Files only in the destination are kept
+    /// # Arguments
+    /// * `src_fs` - Source remote or local path
+    /// * `dst_fs` - Destination remote or local path
+    /// * `rules` - rclone filter rules e.g. `- *.swp`, checked in order
+    /// * `max_depth` - How many directory levels to copy. None copies everything
+    pub fn copy_dir(&self, src_fs: &String, dst_fs: &String, rules: &Vec<String>, max_depth: Option<u32>) -> Result<u64, IOError> {
+        let mut params = json!({"srcFs": src_fs, "dstFs": dst_fs, "createEmptySrcDirs": true});
+        if rules.len() > 0 {
+            params["_filter"] = json!({"FilterRule": rules});
+        }
+        if let Some(depth) = max_depth {
+            params["_config"] = json!({"MaxDepth": depth});
+        }
+        return self.start_job("sync/copy", params);
+    }
+
+    /// Make the destination identical to the source as a job. Files excluded by the rules are neither copied nor deleted
+    /// # Arguments
+    /// * `src_fs` - Source remote or local path
+    /// * `dst_fs` - Destination remote or local path
+    /// * `rules` - rclone filter rules e.g. `- *.swp`, checked in order
+    pub fn sync(&self, src_fs: &String, dst_fs: &String, rules: &Vec<String>) -> Result<u64, IOError> {
+        let mut params = json!({"srcFs": src_fs, "dstFs": dst_fs});
+        if rules.len() > 0 {
+            params["_filter"] = json!({"FilterRule": rules});
+        }
+        return self.start_job("sync/sync", params);
+    }
+
+    /// Create an rclone configuration. The options are sent in the request body, so they don't show up in the process list
+    /// like arguments of `rclone config create` would. Passwords are obscured by rclone
+    /// # Arguments
+    /// * `name` - Name of the configuration
+    /// * `config_type` - rclone backend type e.g. sftp
+    /// * `options` - Values of the backend's options by name
+    /// * `non_interactive` - Fail instead of using a default when the backend would ask a question. Needs rclone 1.56 or newer
+    pub fn create_config(&self, name: &String, config_type: &String, options: &BTreeMap<String, String>, non_interactive: bool) -> Result<(), IOError> {
+        let mut params = json!({"name": name, "type": config_type, "parameters": options});
+        if non_interactive {
+            params["opt"] = json!({"obscure": true, "nonInteractive": true});
+        } else {
+            params["obscure"] = json!(true);
+        }
+        let response = self.call("config/create", &params)?;
+        // a non interactive create returns the question it couldn't answer instead of failing
+        if let Some(error) = response.get("Error").and_then(|e| e.as_str()).filter(|e| e.len() > 0) {
+            return Err(IOError::new(IOErrorKind::InvalidInput, error.to_string()));
+        }
+        if let Some(option) = response.get("Option").and_then(|o| o.get("Name")).and_then(|n| n.as_str()) {
+            return Err(IOError::new(IOErrorKind::InvalidInput, format!("rclone needs a value for the {} option", option)));
+        }
+        return Ok(());
+    }
+
+    /// Get the status of a job
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn job_status(&self, id: u64) -> Result<JobStatus, IOError> {
+        let response = self.call("job/status", &json!({"jobid": id}))?;
+        return serde_json::from_value(response).map_err(|e| IOError::new(IOErrorKind::InvalidData, e.to_string()));
+    }
+
+    /// Cancel a running job
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn stop_job(&self, id: u64) -> Result<(), IOError> {
+        self.call("job/stop", &json!({"jobid": id}))?;
+        return Ok(());
+    }
+
+    /// Get transfer statistics from `core/stats`
+    /// # Arguments
+    /// * `group` - Stats group. Every job has its own group named `job/<id>`. If None the totals of all jobs are returned
+    pub fn stats(&self, group: Option<String>) -> Result<TransferStats, IOError> {
+        let response = match group {
+            Some(group) => self.call("core/stats", &json!({"group": group}))?,
+            None => self.call("core/stats", &json!({}))?
+        };
+        return serde_json::from_value(response).map_err(|e| IOError::new(IOErrorKind::InvalidData, e.to_string()));
+    }
+
+    /// Get the transfer statistics of a single job
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn job_stats(&self, id: u64) -> Result<TransferStats, IOError> {
+        return self.stats(Some(format!("job/{}", id)));
+    }
+
+    /// Get the number of files being transferred or checked by all jobs
+    pub fn active_transfers(&self) -> Result<usize, IOError> {
+        let response = self.call("core/stats", &json!({}))?;
+        let count = |key: &str| response.get(key).and_then(|v| v.as_array()).map_or(0, |v| v.len());
+        return Ok(count("transferring") + count("checking"));
+    }
+
+    /// Change the bandwidth limit of every transfer. rclone only accepts a single rate here, not a timetable
+    /// # Arguments
+    /// * `rate` - Rate e.g. 512k, an upload:download pair or off
+    pub fn set_bwlimit(&self, rate: &String) -> Result<(), IOError> {
+        self.call("core/bwlimit", &json!({"rate": rate}))?;
+        return Ok(());
+    }
+
+    /// Change how many files jobs started from now on transfer and check in parallel
+    /// # Arguments
+    /// * `transfers` - Parallel transfers. 0 restores rclone's default of 4
+    /// * `checkers` - Parallel checks. 0 restores rclone's default of 8
+    pub fn set_concurrency(&self, transfers: u32, checkers: u32) -> Result<(), IOError> {
+        let transfers = if transfers > 0 { transfers } else { 4 };
+        let checkers = if checkers > 0 { checkers } else { 8 };
+        self.call("options/set", &json!({"main": {"Transfers": transfers, "Checkers": checkers}}))?;
+        return Ok(());
+    }
+}
+
+}
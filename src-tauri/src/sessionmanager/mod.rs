@@ -6,13 +6,16 @@
 pub mod rustssh {
 use ssh2::*;
 use std::net::TcpStream;
-use std::io::Read;
-use std::fmt::Debug;
+use std::io::{Read, Write};
+use std::fmt::{Debug, Display, Formatter};
 use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use crate::*;
 
 /// Denotes the RemitFile type
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FileType {
     /// A directory
     TypeDirectory,
@@ -24,6 +27,130 @@ pub enum FileType {
     TypeUnknown
 }
 
+impl FileType {
+    /// Convert the debug name of a type, as sent to the frontend, back into the enum. e.g. `TypeDirectory`
+    /// # Arguments
+    /// * `input` - Name of the type
+    pub fn parse(input: &str) -> Option<FileType> {
+        match input {
+            "TypeDirectory"=> return Some(FileType::TypeDirectory),
+            "TypeFile"=> return Some(FileType::TypeFile),
+            "TypeLink"=> return Some(FileType::TypeLink),
+            "TypeUnknown"=> return Some(FileType::TypeUnknown),
+            _=> return None
+        }
+    }
+}
+
+/// Field used to order a directory listing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    /// Sort by name. Numbers in names are compared by value
+    Name,
+    /// Sort by size in bytes
+    Size,
+    /// Sort by file type, then by name
+    Type
+}
+
+impl SortKey {
+    /// Convert a string into a sort key. Accepts `name`, `size` and `type`
+    /// # Arguments
+    /// * `input` - Name of the sort key
+    pub fn parse(input: &str) -> Option<SortKey> {
+        match input.to_lowercase().as_str() {
+            "name"=> return Some(SortKey::Name),
+            "size"=> return Some(SortKey::Size),
+            "type"=> return Some(SortKey::Type),
+            _=> return None
+        }
+    }
+}
+
+impl Display for SortKey {
+    /// Write the name of the sort key as accepted by [`SortKey::parse`]
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            SortKey::Name=> "name",
+            SortKey::Size=> "size",
+            SortKey::Type=> "type"
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+/// Options controlling which files of a directory are returned and in what order
+/// 
+/// The `.` and `..` entries are always returned first and are never filtered out
+#[derive(Debug, Clone)]
+pub struct ListingOptions {
+    /// Field to sort by
+    pub sort_key: SortKey,
+    /// Reverse the sort order
+    pub descending: bool,
+    /// Place all directories before any other files
+    pub dirs_first: bool,
+    /// Include files starting with a `.`
+    pub show_hidden: bool,
+    /// Only include files whose name contains this text. Case insensitive
+    pub name_filter: Option<String>,
+    /// Only include files of this type
+    pub type_filter: Option<FileType>
+}
+
+impl ListingOptions {
+    /// Create listing options that return every file sorted by name
+    pub fn new() -> ListingOptions {
+        return ListingOptions{sort_key: SortKey::Name, descending: false, dirs_first: false,
+                              show_hidden: true, name_filter: None, type_filter: None};
+    }
+}
+
+/// Compare 2 names so that runs of digits are compared by their value rather than character by character.
+/// For example, `file2` comes before `file10`. Text is compared without case
+/// # Arguments
+/// * `a` - First name
+/// * `b` - Second name
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                if x.is_ascii_digit() && y.is_ascii_digit() {
+                    let mut x_num = String::new();
+                    let mut y_num = String::new();
+                    while let Some(c) = a_chars.peek().copied().filter(|c| c.is_ascii_digit()) {
+                        x_num.push(c);
+                        a_chars.next();
+                    }
+                    while let Some(c) = b_chars.peek().copied().filter(|c| c.is_ascii_digit()) {
+                        y_num.push(c);
+                        b_chars.next();
+                    }
+                    // compare without leading zeros by length first so long numbers don't overflow
+                    let x_trim = x_num.trim_start_matches('0');
+                    let y_trim = y_num.trim_start_matches('0');
+                    let ordering = x_trim.len().cmp(&y_trim.len()).then(x_trim.cmp(y_trim));
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                } else {
+                    let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                    a_chars.next();
+                    b_chars.next();
+                }
+            }
+        }
+    }
+}
+
 /// Permission information for a file
 /// 
 /// Each file has 3 permissions objects - owner, group, other
@@ -123,6 +250,24 @@ impl RemitFile {
         info.file_type = file_type.unwrap_or(FileType::TypeUnknown);
         return RemitFile{info: info};
     }
+
+    /// Create a RemitFile object from an sftp stat result
+    /// # Arguments
+    /// * `name` - Name of the file
+    /// * `stat` - The stat information returned by the sftp subsystem
+    pub fn from_stat(name: String, stat: &FileStat) -> RemitFile {
+        let file_type;
+        if stat.file_type().is_symlink() {
+            file_type = FileType::TypeLink;
+        } else if stat.is_dir() {
+            file_type = FileType::TypeDirectory;
+        } else if stat.is_file() {
+            file_type = FileType::TypeFile;
+        } else {
+            file_type = FileType::TypeUnknown;
+        }
+        return RemitFile::new_populated(name, stat.size, Some(file_type));
+    }
 }
 
 /// represents a directory and its contents
@@ -155,6 +300,54 @@ impl Directory {
         return dir;
     }
 
+    /// Get the files in this directory filtered and sorted by the given options
+    /// # Arguments
+    /// * `options` - Filtering and sorting options
+    pub fn list(&self, options: &ListingOptions) -> Vec<RemitFile> {
+        let mut special: Vec<RemitFile> = Vec::new();
+        let mut files: Vec<RemitFile> = Vec::new();
+        let name_filter = options.name_filter.clone().map(|f| f.to_lowercase());
+        for entry in &self.files {
+            let name = entry.0.as_str();
+            if name == "." || name == ".." {
+                special.push(entry.1.clone());
+                continue;
+            }
+            if !options.show_hidden && name.starts_with('.') {
+                continue;
+            }
+            if name_filter.as_ref().map_or(false, |f| !name.to_lowercase().contains(f.as_str())) {
+                continue;
+            }
+            if options.type_filter.as_ref().map_or(false, |t| *t != entry.1.info.file_type) {
+                continue;
+            }
+            files.push(entry.1.clone());
+        }
+        files.sort_by(|a, b| {
+            if options.dirs_first {
+                let a_dir = a.info.file_type == FileType::TypeDirectory;
+                let b_dir = b.info.file_type == FileType::TypeDirectory;
+                if a_dir != b_dir {
+                    return b_dir.cmp(&a_dir);
+                }
+            }
+            let ordering = match options.sort_key {
+                SortKey::Name=> natural_cmp(&a.info.name, &b.info.name),
+                SortKey::Size=> a.info.size.cmp(&b.info.size).then(natural_cmp(&a.info.name, &b.info.name)),
+                SortKey::Type=> format!("{:?}", a.info.file_type).cmp(&format!("{:?}", b.info.file_type))
+                                    .then(natural_cmp(&a.info.name, &b.info.name))
+            };
+            if options.descending {
+                return ordering.reverse();
+            }
+            return ordering;
+        });
+        special.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+        special.append(&mut files);
+        return special;
+    }
+
     /// Parse a string to create the file structure in a directory
     /// # Arguments
     /// * `input` - The output of a `stat .* * --printf='Name: %n\\nPermissions: %a\\nSize: %s\\nType: %F\\n\\n` command
@@ -289,6 +482,25 @@ impl SessionManager {
         return Ok(s.trim_end().to_string());
     }
 
+    /// Run an ssh command on the remote machine and wait for it to exit. Unlike [`SessionManager::run_command`], a non zero exit
+    /// status is returned as an error containing the command's stderr
+    /// # Arguments
+    /// * `command` - Command to be ran on the remote machine
+    pub fn run_command_checked(&mut self, command: String) -> Result<String, IOError>{
+        let mut channel: ssh2::Channel = self.session.channel_session()?;
+        channel.exec(command.as_str())?;
+        let mut s = String::new();
+        channel.read_to_string(&mut s)?;
+        let mut error = String::new();
+        channel.stderr().read_to_string(&mut error)?;
+        channel.wait_close()?;
+        let status = channel.exit_status()?;
+        if status != 0 {
+            return Err(IOError::new(IOErrorKind::Other, format!("{} exited with status {}: {}", command, status, error.trim_end())));
+        }
+        return Ok(s.trim_end().to_string());
+    }
+
     /// Load the file contents of the directory into that directory
     /// 
     /// Parses a stat command - see source for full command - at the directory's path. This will store file information
@@ -302,6 +514,104 @@ impl SessionManager {
         return Ok(());
     }
 
+    /// Read the contents of a directory through sftp and hand them back in pages
+    /// 
+    /// Unlike [`SessionManager::get_directory`], the listing is never fully held in memory. Entries are read one at a time
+    /// with sftp readdir and passed to `on_page` once `page_size` entries have been collected. Before each entry the status is
+    /// checked and if it is set to [`ThreadStatus::Kill`] the listing stops with an Interrupted error. Returns the total number
+    /// of entries read
+    /// # Arguments
+    /// * `path` - Remote path of the directory to read
+    /// * `page_size` - Number of entries in each page. The last page may be smaller
+    /// * `status` - Shared status used to cancel the listing
+    /// * `on_page` - Called with each page of files
+    pub fn stream_directory(&mut self, path: &Remit::SystemPath, page_size: usize, status: &Arc::<Mutex::<ThreadStatus>>,
+                            on_page: &mut dyn FnMut(Vec<RemitFile>) -> Result<(), IOError>) -> Result<u64, IOError>{
+        let sftp = self.session.sftp()?;
+        let mut handle = sftp.opendir(Path::new(&path.get_path()))?;
+        let mut page: Vec<RemitFile> = Vec::with_capacity(page_size);
+        let mut total = 0u64;
+        loop {
+            if *status.lock().unwrap() == ThreadStatus::Kill {
+                return Err(IOError::new(IOErrorKind::Interrupted, "listing cancelled"));
+            }
+            match handle.readdir() {
+                Ok((entry_path, stat)) => {
+                    let name = entry_path.file_name().map(|n| n.to_string_lossy().to_string())
+                                                     .unwrap_or(entry_path.to_string_lossy().to_string());
+                    page.push(RemitFile::from_stat(name, &stat));
+                    total += 1;
+                    if page.len() >= page_size {
+                        on_page(std::mem::replace(&mut page, Vec::with_capacity(page_size)))?;
+                    }
+                },
+                // libssh2 reports the end of a directory as a file error
+                Err(e) if e.code() == ErrorCode::Session(-16) => break,
+                Err(e) => return Err(IOError::from(e))
+            }
+        }
+        if page.len() > 0 {
+            on_page(page)?;
+        }
+        return Ok(total);
+    }
+
+    /// Read up to max_bytes from the start of a remote file through sftp. Returns the bytes read and the full size of the file
+    /// # Arguments
+    /// * `path` - Remote path of the file
+    /// * `max_bytes` - Maximum number of bytes to read
+    pub fn read_remote_bytes(&mut self, path: &String, max_bytes: u64) -> Result<(Vec<u8>, u64), IOError>{
+        let sftp = self.session.sftp()?;
+        let remote_path = Path::new(path);
+        let size = sftp.stat(remote_path)?.size.unwrap_or(0);
+        let file = sftp.open(remote_path)?;
+        let mut bytes: Vec<u8> = Vec::new();
+        file.take(max_bytes).read_to_end(&mut bytes)?;
+        return Ok((bytes, size));
+    }
+
+    /// Get a version string for a remote file from a hash of its contents, computed on the server with sha1sum or md5sum if it isn't
+    /// available. Unlike the modification time, the hash changes even when a file is rewritten with the same size in the same
+    /// second, so it can be used to detect changes made by someone else
+    /// # Arguments
+    /// * `path` - Remote path of the file
+    pub fn remote_version(&mut self, path: &String) -> Result<String, IOError>{
+        let output = self.run_command_checked(format!("if command -v sha1sum >/dev/null; then sha1sum \"{}\"; else md5sum \"{}\"; fi", path, path))?;
+        let hash = output.split_whitespace().next().unwrap_or("");
+        if hash.len() == 0 {
+            return Err(IOError::new(IOErrorKind::Other, format!("Could not hash {}", path)));
+        }
+        return Ok(hash.to_string());
+    }
+
+    /// Replace the contents of a remote file atomically
+    /// 
+    /// The bytes are written through sftp to a temporary file next to the destination, which is then moved over the destination
+    /// with `mv`. Readers never see a partially written file. The permissions of the existing file are kept
+    /// # Arguments
+    /// * `path` - Remote path of the file
+    /// * `bytes` - New contents of the file
+    pub fn write_remote_bytes(&mut self, path: &Remit::SystemPath, bytes: &[u8]) -> Result<(), IOError>{
+        let mut temp_path = path.clone();
+        let name = temp_path.popd();
+        temp_path.pushd(format!(".{}.remit-tmp", name));
+        let sftp = self.session.sftp()?;
+        {
+            let mut file = sftp.create(Path::new(&temp_path.get_path()))?;
+            file.write_all(bytes)?;
+        }
+        if let Ok(stat) = sftp.stat(Path::new(&path.get_path())) {
+            if let Some(perm) = stat.perm {
+                let _r = self.run_command(format!("chmod {:o} \"{}\"", perm & 0o7777, temp_path.get_path()));
+            }
+        }
+        let r = self.run_command_checked(format!("mv -f \"{}\" \"{}\"", temp_path.get_path(), path.get_path()));
+        if r.is_err() {
+            let _r = sftp.unlink(Path::new(&temp_path.get_path()));
+        }
+        return r.map(|_| ());
+    }
+
     /// Push the name onto the directory path essentially "navigating" to that path.
     /// 
     /// This method does not update the file contents, only modifies the path. Additionally, before navigating, the
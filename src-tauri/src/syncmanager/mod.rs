@@ -2,71 +2,491 @@
 //! it makes common rsync commands. With an accessible rclone executable, this module can construct 
 //! queries to manage rclone configurations and push/pull files
 //! 
+//! Besides sftp, configurations can use the s3, webdav, ftp and local backends. These have no ssh session, so their directories
+//! are listed with `rclone lsjson` and changed with rclone's mkdir, touch, deletefile, purge and moveto commands
+//! 
+//! The version of the rclone executable is read with `rclone version` when it is found. Executables older than [`rustssh::MIN_VERSION`]
+//! aren't used at all, newer ones without a feature like bisync still work but give an error when that feature is used
+//! 
 pub mod rustssh {
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::process::Command;
 use std::string::ToString;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
+#[cfg(windows)]
 use std::os::windows::process::CommandExt;
-use std::fs::metadata;
+use std::env::{consts, current_exe, split_paths, var_os};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+use serde::Deserialize;
 use crate::*;
 
 /// MS Windows flag for process creation. This flag prevents a console window from appearing when doing
 /// callouts to the rclone exe
+#[cfg(windows)]
 static CREATE_NO_WINDOW: u32 = 0x08000000;
 
-/// Represents an rclone configuration
-#[derive(Clone, Debug)]
+/// Name rclone is looked up by in the sidecar directory and on the PATH
+static RCLONE_NAME: &str = "rclone";
+
+/// Keep a console window from appearing for a child process. Only does something on MS Windows
+/// # Arguments
+/// * `command` - Command to change
+#[cfg(windows)]
+pub fn hide_window(command: &mut Command) -> &mut Command {
+    return command.creation_flags(CREATE_NO_WINDOW);
+}
+
+/// Keep a console window from appearing for a child process. Only does something on MS Windows
+/// # Arguments
+/// * `command` - Command to change
+#[cfg(not(windows))]
+pub fn hide_window(command: &mut Command) -> &mut Command {
+    return command;
+}
+
+/// Represents an rclone configuration as output by `rclone config dump`. Every option of the backend is kept, the common
+/// sftp options are also available as fields
+#[derive(Clone, Debug, Deserialize)]
 pub struct RCloneConfig {
-    /// Type of rclone configuration e.g. sftp
-    config_type: String,
-    /// configuration name
+    /// configuration name. This is the key of the configuration in the dump
+    #[serde(skip)]
     pub name: String,
-    host: String,
-    user: String,
-    pass: String
+    /// Type of rclone configuration e.g. sftp
+    #[serde(rename = "type")]
+    pub config_type: String,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub user: String,
+    /// Password obscured by rclone
+    #[serde(default)]
+    pub pass: String,
+    #[serde(default)]
+    pub port: String,
+    /// All other backend options stored by option name
+    #[serde(flatten)]
+    pub options: BTreeMap<String, String>
+}
+
+/// rclone backends Remit can create configurations for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    Sftp,
+    /// Amazon S3 and S3 compatible storage like MinIO
+    S3,
+    WebDav,
+    Ftp,
+    /// A directory on this computer
+    Local
+}
+
+impl Backend {
+    /// Every backend in the order they are offered
+    pub fn all() -> Vec<Backend> {
+        return vec![Backend::Sftp, Backend::S3, Backend::WebDav, Backend::Ftp, Backend::Local];
+    }
+
+    /// Parse an rclone backend type e.g. webdav
+    /// # Arguments
+    /// * `input` - rclone type name
+    pub fn parse(input: &str) -> Option<Backend> {
+        match input {
+            "sftp"=> return Some(Backend::Sftp),
+            "s3"=> return Some(Backend::S3),
+            "webdav"=> return Some(Backend::WebDav),
+            "ftp"=> return Some(Backend::Ftp),
+            "local"=> return Some(Backend::Local),
+            _=> return None
+        }
+    }
+
+    /// Get the rclone options a configuration of this backend can set
+    pub fn options(&self) -> Vec<&'static str> {
+        match self {
+            Backend::Sftp=> return vec!["host", "user", "pass", "port", "key_file"],
+            Backend::S3=> return vec!["provider", "access_key_id", "secret_access_key", "endpoint", "region", "env_auth"],
+            Backend::WebDav=> return vec!["url", "vendor", "user", "pass", "bearer_token"],
+            Backend::Ftp=> return vec!["host", "user", "pass", "port", "tls", "explicit_tls"],
+            Backend::Local=> return Vec::new()
+        }
+    }
+
+    /// Get the options a configuration of this backend must set
+    pub fn required_options(&self) -> Vec<&'static str> {
+        match self {
+            Backend::Sftp | Backend::Ftp=> return vec!["host"],
+            Backend::S3=> return vec!["provider"],
+            Backend::WebDav=> return vec!["url"],
+            Backend::Local=> return Vec::new()
+        }
+    }
+
+    /// True if configurations of this backend are browsed over ssh
+    pub fn has_ssh(&self) -> bool {
+        return *self == Backend::Sftp;
+    }
+}
+
+impl Display for Backend {
+    /// Write the rclone type name
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            Backend::Sftp=> "sftp",
+            Backend::S3=> "s3",
+            Backend::WebDav=> "webdav",
+            Backend::Ftp=> "ftp",
+            Backend::Local=> "local"
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+/// An entry of `rclone lsjson`
+#[derive(Debug, Deserialize)]
+struct ListEntry {
+    #[serde(rename = "Name")]
+    name: String,
+    /// -1 for directories and unknown sizes
+    #[serde(rename = "Size", default)]
+    size: i64,
+    #[serde(rename = "IsDir", default)]
+    is_dir: bool
 }
 
 impl RCloneConfig {
-    /// Create a new RCloneConfiguration object
+    /// Get the backend of the configuration. None for backends Remit doesn't create
+    pub fn backend(&self) -> Option<Backend> {
+        return Backend::parse(&self.config_type);
+    }
+
+    /// Create an empty configuration
+    /// # Arguments
+    /// * `name` - Name of the configuration
+    /// * `config_type` - rclone backend type e.g. sftp
+    pub fn new(name: String, config_type: String) -> RCloneConfig {
+        return RCloneConfig{name: name, config_type: config_type, host: String::new(), user: String::new(),
+                            pass: String::new(), port: String::new(), options: BTreeMap::new()};
+    }
+
+    /// Parse the JSON output of `rclone config dump`, an object of configurations keyed by name
     /// # Arguments
-    /// * `input` - The text source of an rclone configuration. None creates an empty configuration
-    pub fn new(input: Option<String>) -> Result<RCloneConfig, IOError> {
-        if input.is_none() {
-            return Ok(RCloneConfig{config_type: String::new(),
-                                name: String::new(),
-                                host: String::new(),
-                                user: String::new(),
-                                pass: String::new()});
+    /// * `input` - Output of `rclone config dump`. An empty string means there are no configurations
+    pub fn parse_dump(input: &str) -> Result<Vec<RCloneConfig>, IOError>{
+        if input.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let dump: BTreeMap<String, RCloneConfig> = serde_json::from_str(input)
+            .map_err(|e| IOError::new(IOErrorKind::InvalidData, format!("Error parsing rclone config dump: {}", e)))?;
+        let mut configs = Vec::new();
+        for (name, mut config) in dump {
+            config.name = name;
+            configs.push(config);
+        }
+        return Ok(configs);
+    }
+}
+
+/// Kind of change a dry run reports
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlannedAction {
+    /// A file would be copied, either because it is new or because it differs
+    Copy,
+    /// A file would be deleted from the destination
+    Delete,
+    /// Only the modification time of an identical file would be updated
+    Update,
+    /// A directory would be created
+    MakeDir,
+    /// A directory would be removed
+    RemoveDir
+}
+
+impl Display for PlannedAction {
+    /// Write the short name of the action
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            PlannedAction::Copy=> "copy",
+            PlannedAction::Delete=> "delete",
+            PlannedAction::Update=> "update",
+            PlannedAction::MakeDir=> "mkdir",
+            PlannedAction::RemoveDir=> "rmdir"
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+/// A change rclone would make, reported by a dry run
+#[derive(Clone, Debug)]
+pub struct PlannedChange {
+    pub action: PlannedAction,
+    /// Path of the file or directory relative to the destination
+    pub path: String,
+    /// Size of a copied file in bytes if rclone reported it
+    pub size: Option<u64>
+}
+
+/// Parse the JSON log of an rclone command run with `--dry-run --use-json-log`. Each skipped operation is logged as a notice like
+/// `Skipped copy as --dry-run is set (size 12)` with the file in the object field. Lines that aren't JSON are ignored
+/// # Arguments
+/// * `log` - stderr of the rclone command
+pub fn parse_dry_run(log: &str) -> Vec<PlannedChange> {
+    let mut changes: Vec<PlannedChange> = Vec::new();
+    for line in log.lines() {
+        let entry: serde_json::Value = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(_) => continue
+        };
+        let msg = entry.get("msg").and_then(|m| m.as_str()).unwrap_or("");
+        if !msg.contains("--dry-run") {
+            continue;
         }
-        return RCloneConfig::parse_config(input.unwrap());
+        let action = if msg.contains("update modification time") {
+            PlannedAction::Update
+        } else if msg.contains("copy") {
+            PlannedAction::Copy
+        } else if msg.contains("make directory") {
+            PlannedAction::MakeDir
+        } else if msg.contains("remove directory") {
+            PlannedAction::RemoveDir
+        } else if msg.contains("delete") {
+            PlannedAction::Delete
+        } else {
+            continue;
+        };
+        let size = msg.split("(size ").nth(1).and_then(|s| parse_size(s.trim_end_matches(')').trim()));
+        let path = entry.get("object").and_then(|o| o.as_str()).unwrap_or("").to_string();
+        changes.push(PlannedChange{action: action, path: path, size: size});
+    }
+    return changes;
+}
+
+/// Parse a size printed by rclone e.g. 12, 1.500Ki or 3Mi. Sizes with a binary suffix are rounded to the nearest byte
+/// # Arguments
+/// * `size` - Size text
+fn parse_size(size: &str) -> Option<u64> {
+    let split = size.find(|c: char| c.is_alphabetic()).unwrap_or(size.len());
+    let (number, suffix) = size.split_at(split);
+    let multiplier: f64 = match suffix.trim_end_matches('B').trim_end_matches('i') {
+        ""=> 1.0,
+        "K"=> 1024.0,
+        "M"=> 1024.0 * 1024.0,
+        "G"=> 1024.0 * 1024.0 * 1024.0,
+        "T"=> 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _=> return None
+    };
+    return number.parse::<f64>().ok().map(|n| (n * multiplier).round() as u64);
+}
+
+/// Limits which files a directory transfer copies
+#[derive(Clone, Debug)]
+pub struct DirectoryFilter {
+    /// How many directory levels to descend. 1 only copies the files directly in the directory. None copies everything
+    pub max_depth: Option<u32>,
+    /// Glob patterns of files to copy e.g. *.rs or src/**. If empty every file not excluded is copied
+    pub include: Vec<String>,
+    /// Glob patterns of files to skip. Checked before the ignore rules and the include patterns
+    pub exclude: Vec<String>
+}
+
+impl DirectoryFilter {
+    /// Create a filter that copies everything
+    pub fn new() -> DirectoryFilter {
+        return DirectoryFilter{max_depth: None, include: Vec::new(), exclude: Vec::new()};
     }
 
-    /// Parse the information contained in an rclone configuration
+    /// Convert the patterns into rclone filter rules around the rules of the ignore files. rclone doesn't allow mixing --include
+    /// and --exclude so both are expressed as `- pattern` and `+ pattern` rules, followed by `- **` when there are include patterns
+    ///
+    /// rclone applies the first rule that matches. The excludes come first so a `!pattern` in an ignore file can't bring back a
+    /// file the user excluded, and the includes last so an included file is still skipped if it is ignored
     /// # Arguments
-    /// * `input` - rclone configuration file text
-    fn parse_config(input: String) -> Result<RCloneConfig, IOError>{
-        let mut config = RCloneConfig::new(None).unwrap();
-        for line in input.lines() {
-            // process name of config
-            if line.len() <= 2 {
-                continue;
+    /// * `ignore_rules` - rclone filter rules of the ignore files, see [`RCloneManager::ignore_filter`]
+    pub fn filter_rules(&self, ignore_rules: Vec<String>) -> Vec<String> {
+        let mut rules: Vec<String> = Vec::new();
+        for pattern in &self.exclude {
+            rules.push(format!("- {}", pattern));
+        }
+        rules.extend(ignore_rules);
+        for pattern in &self.include {
+            rules.push(format!("+ {}", pattern));
+        }
+        if self.include.len() > 0 {
+            rules.push("- **".to_string());
+        }
+        return rules;
+    }
+}
+
+/// Bandwidth and concurrency limits passed to rclone
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferLimits {
+    /// rclone --bwlimit value. Either a single rate e.g. 512k, an upload:download pair e.g. 1M:5M, or a timetable of
+    /// `[Day-]HH:MM,rate` entries e.g. `08:00,512k 19:00,off`. Empty means unlimited
+    pub bwlimit: String,
+    /// Number of files transferred in parallel. 0 uses rclone's default
+    pub transfers: u32,
+    /// Number of files checked in parallel. 0 uses rclone's default
+    pub checkers: u32
+}
+
+impl TransferLimits {
+    /// Create limits that leave rclone's defaults alone
+    pub fn new() -> TransferLimits {
+        return TransferLimits{bwlimit: String::new(), transfers: 0, checkers: 0};
+    }
+
+    /// Check that the bandwidth limit is a rate or a timetable rclone accepts
+    pub fn validate(&self) -> Result<(), IOError> {
+        let entries: Vec<&str> = self.bwlimit.split_whitespace().collect();
+        if entries.len() == 1 && !entries[0].contains(',') {
+            return TransferLimits::validate_rate(entries[0]);
+        }
+        for entry in entries {
+            let (time, rate) = entry.split_once(',').ok_or(IOError::new(IOErrorKind::InvalidInput,
+                                                          format!("Timetable entry {} must look like HH:MM,rate", entry)))?;
+            let time = match time.split_once('-') {
+                Some((day, time)) => {
+                    let days = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+                    if !days.iter().any(|d| day.to_lowercase().starts_with(d)) {
+                        return Err(IOError::new(IOErrorKind::InvalidInput, format!("Unknown day {} in timetable", day)));
+                    }
+                    time
+                },
+                None => time
+            };
+            let valid_time = match time.split_once(':') {
+                Some((hours, minutes)) => hours.parse::<u32>().map_or(false, |h| h < 24) && minutes.len() == 2
+                                            && minutes.parse::<u32>().map_or(false, |m| m < 60),
+                None => false
+            };
+            if !valid_time {
+                return Err(IOError::new(IOErrorKind::InvalidInput, format!("Invalid time {} in timetable", time)));
             }
-            if line.chars().nth(0).unwrap() == '[' {
-                config.name = line[1..line.len()-1].to_string();
-            } else {
-                let mut categories = line.split_whitespace();
-                match categories.nth(0).ok_or(IOError::new(IOErrorKind::UnexpectedEof, "Error parsing configuration file"))? {
-                    "type"=> config.config_type = categories.nth(1).ok_or(IOError::new(IOErrorKind::UnexpectedEof, "Error parsing configuration file"))?.to_string(),
-                    "host"=> config.host = categories.nth(1).ok_or(IOError::new(IOErrorKind::UnexpectedEof, "Error parsing configuration file"))?.to_string(),
-                    "pass"=> config.pass = categories.nth(1).ok_or(IOError::new(IOErrorKind::UnexpectedEof, "Error parsing configuration file"))?.to_string(),
-                    "user"=> config.user = categories.nth(1).ok_or(IOError::new(IOErrorKind::UnexpectedEof, "Error parsing configuration file"))?.to_string(),
-                    _=>{}
+            TransferLimits::validate_rate(rate)?;
+        }
+        return Ok(());
+    }
 
-                }
+    /// Check a rate like 512k, 1.5M, off or an upload:download pair of them
+    /// # Arguments
+    /// * `rate` - Rate text
+    fn validate_rate(rate: &str) -> Result<(), IOError> {
+        for part in rate.split(':') {
+            let number = part.trim_end_matches(|c: char| "bBkKmMgGtTpP".contains(c));
+            let suffix = &part[number.len()..];
+            if part != "off" && (suffix.len() > 1 || number.parse::<f64>().map_or(true, |n| n < 0.0)) {
+                return Err(IOError::new(IOErrorKind::InvalidInput, format!("Invalid bandwidth {}", rate)));
             }
         }
-        return Ok(config);
+        return Ok(());
+    }
+
+    /// True if the bandwidth limit changes with the time of day
+    pub fn is_timetable(&self) -> bool {
+        return self.bwlimit.contains(',');
+    }
+
+    /// Get the rclone flags applying these limits
+    pub fn args(&self) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        if self.bwlimit.trim().len() > 0 {
+            args.push(format!("--bwlimit={}", self.bwlimit.trim()));
+        }
+        if self.transfers > 0 {
+            args.push(format!("--transfers={}", self.transfers));
+        }
+        if self.checkers > 0 {
+            args.push(format!("--checkers={}", self.checkers));
+        }
+        return args;
+    }
+}
+
+/// Oldest rclone release Remit works with. Older releases don't give every async job of the daemon its own stats group, which
+/// transfer progress is read from
+pub static MIN_VERSION: RCloneVersion = RCloneVersion{major: 1, minor: 49, patch: 0};
+
+/// Version of an rclone executable as printed by `rclone version`. Pre-release suffixes like -beta.7480 are dropped
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RCloneVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32
+}
+
+impl RCloneVersion {
+    /// Parse the output of `rclone version`. Its first line looks like `rclone v1.64.2`
+    /// # Arguments
+    /// * `output` - stdout of rclone version
+    pub fn parse(output: &str) -> Option<RCloneVersion> {
+        let line = output.lines().next()?;
+        let word = line.split_whitespace().map(|w| w.trim_start_matches('v')).find(|w| w.starts_with(|c: char| c.is_ascii_digit()))?;
+        let mut numbers = word.split(|c: char| !c.is_ascii_digit());
+        let major = numbers.next()?.parse::<u32>().ok()?;
+        let minor = numbers.next()?.parse::<u32>().ok()?;
+        let patch = numbers.next().and_then(|n| n.parse::<u32>().ok()).unwrap_or(0);
+        return Some(RCloneVersion{major: major, minor: minor, patch: patch});
+    }
+
+    /// Check if this version can run a feature
+    /// # Arguments
+    /// * `feature` - Feature to check
+    pub fn supports(&self, feature: RCloneFeature) -> bool {
+        return *self >= feature.min_version();
+    }
+}
+
+impl Display for RCloneVersion {
+    /// Write the version the way rclone prints it, e.g. `v1.64.2`
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        return write!(f, "v{}.{}.{}", self.major, self.minor, self.patch);
+    }
+}
+
+/// rclone features Remit uses that are newer than [`MIN_VERSION`]. Older executables run without them, see [`RCloneManager::supports`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RCloneFeature {
+    /// Non interactive `config/create`. Without it configurations are created the older way, which doesn't ask questions either
+    /// but can't report what went wrong as clearly
+    NonInteractiveConfig,
+    /// `lsjson --stat`, used to check that a remote path is a directory without listing it
+    StatJson,
+    /// `rclone bisync` for two way sync pairs
+    Bisync,
+    /// `--use-json-log`, which transfer previews are parsed from
+    JsonLog
+}
+
+impl RCloneFeature {
+    /// Get every feature
+    pub fn all() -> Vec<RCloneFeature> {
+        return vec![RCloneFeature::NonInteractiveConfig, RCloneFeature::StatJson, RCloneFeature::Bisync, RCloneFeature::JsonLog];
+    }
+
+    /// Get the first rclone release with the feature
+    pub fn min_version(&self) -> RCloneVersion {
+        return match self {
+            RCloneFeature::NonInteractiveConfig => RCloneVersion{major: 1, minor: 56, patch: 0},
+            RCloneFeature::StatJson => RCloneVersion{major: 1, minor: 56, patch: 0},
+            RCloneFeature::Bisync => RCloneVersion{major: 1, minor: 58, patch: 0},
+            RCloneFeature::JsonLog => RCloneVersion{major: 1, minor: 50, patch: 0}
+        };
+    }
+}
+
+impl Display for RCloneFeature {
+    /// Write the feature name reported to the frontend
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            RCloneFeature::NonInteractiveConfig=> "non_interactive_config",
+            RCloneFeature::StatJson=> "stat_json",
+            RCloneFeature::Bisync=> "bisync",
+            RCloneFeature::JsonLog=> "json_log"
+        };
+        return write!(f, "{}", name);
     }
 }
 
@@ -75,52 +495,203 @@ impl RCloneConfig {
 /// This manager converts common necessary functions used by Remit into 
 /// appropriate commands to run in rclone and then parses the output
 pub struct RCloneManager {
-    /// The name of the rclone executable
-    exe: String,
+    /// Path of the rclone executable set by the user. Looked at before the sidecar directory and the PATH
+    configured_exe: Option<String>,
+    /// Path of the rclone executable commands are run with. None if rclone couldn't be found, see [`RCloneManager::find_exe`]
+    exe: Option<PathBuf>,
     /// A map of rclone configurations. These are stored by name
     configs: HashMap<String, RCloneConfig>,   
     /// The currently chosen configuration by name
     pub chosen_config: String,
-    /// Path which contains the rclone executable
-    custom_path: String
+    /// Directory local paths are relative to. Empty for the current directory
+    custom_path: String,
+    /// If true, files copied by [`RCloneManager::download_remote_file`] and [`RCloneManager::upload_local_file`] are checked against
+    /// the source and copied again if they differ
+    verify: bool,
+    /// Number of times a file that fails verification is copied again before giving up
+    verify_retries: u32,
+    /// Bandwidth and concurrency limits of every rclone command and the daemon
+    limits: Remit::TransferLimits,
+    /// gitignore style patterns of the configuration, see [`Remit::IgnoreRules`]
+    ignore_patterns: Vec<String>,
+    /// Set when the daemon has to be restarted to pick up a new bandwidth timetable or rclone executable. Done by [`RCloneManager::start_daemon`]
+    /// once no transfers are running
+    restart_daemon: bool,
+    /// Version of the rclone executable, read when it is found. None if rclone couldn't be run
+    version: Option<RCloneVersion>,
+    /// Long lived rclone process used for async transfers. None until [`RCloneManager::start_daemon`] is called
+    daemon: Option<Remit::RCloneDaemon>
 }
 #[allow(dead_code)]
 impl RCloneManager {
-    /// Produces a new rlcone manager. If input is none assume rclone.exe is in the current
-    /// directory
+    /// Produces a new rlcone manager, look for the rclone executable and read its version, see [`RCloneManager::find_exe`]
     /// # Arguments
-    /// * `exe` - Name of the rclone executable, if None assume rclone.exe
-    /// * `custom_path` - Path to this executable. If None, assume in the current directory
+    /// * `exe` - Path of the rclone executable or of the directory containing it. If None, look in the sidecar directory and on the PATH
+    /// * `custom_path` - Directory local paths are relative to. If None, use the current directory
     pub fn new(exe: Option<String>, custom_path: Option<String>) -> RCloneManager{
-        return RCloneManager{exe: exe.unwrap_or("rclone.exe".to_string()), configs: HashMap::new(), chosen_config: String::new(),
-                                custom_path: custom_path.unwrap_or("".to_string())};
+        let mut m = RCloneManager{exe: RCloneManager::find_exe(&exe), configured_exe: exe, configs: HashMap::new(), chosen_config: String::new(),
+                                custom_path: custom_path.unwrap_or("".to_string()), daemon: None, version: None,
+                                verify: false, verify_retries: 1, limits: Remit::TransferLimits::new(), restart_daemon: false,
+                                ignore_patterns: Vec::new()};
+        m.version = m.detect_version().ok();
+        return m;
+    }
+
+    /// Look for the rclone executable. In order, these are tried:
+    /// 1. The configured path. If it is a directory, the sidecar names are looked for inside it
+    /// 2. The directory of the Remit executable, where Tauri puts the `bin/rclone` sidecar. Both `rclone` and the
+    /// `rclone-<target triple>` name used in the source tree are accepted
+    /// 3. Every directory on the PATH
+    /// # Arguments
+    /// * `configured` - Path set by the user, if any
+    pub fn find_exe(configured: &Option<String>) -> Option<PathBuf> {
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        if let Some(path) = configured.as_ref().filter(|p| p.len() > 0) {
+            let path = PathBuf::from(path);
+            if path.is_dir() {
+                candidates.extend(RCloneManager::sidecar_names().iter().map(|name| path.join(name)));
+            }
+            candidates.push(path);
+        }
+        if let Some(dir) = current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.to_path_buf())) {
+            candidates.extend(RCloneManager::sidecar_names().iter().map(|name| dir.join(name)));
+        }
+        if let Some(paths) = var_os("PATH") {
+            candidates.extend(split_paths(&paths).map(|dir| dir.join(format!("{}{}", RCLONE_NAME, consts::EXE_SUFFIX))));
+        }
+        return candidates.into_iter().find(|candidate| candidate.is_file());
+    }
+
+    /// File names the rclone sidecar can have on this platform e.g. rclone.exe and rclone-x86_64-pc-windows-msvc.exe
+    fn sidecar_names() -> Vec<String> {
+        let target = if cfg!(windows) {
+            "pc-windows-msvc"
+        } else if cfg!(target_os = "macos") {
+            "apple-darwin"
+        } else {
+            "unknown-linux-gnu"
+        };
+        return vec![format!("{}{}", RCLONE_NAME, consts::EXE_SUFFIX),
+                    format!("{}-{}-{}{}", RCLONE_NAME, consts::ARCH, target, consts::EXE_SUFFIX)];
+    }
+
+    /// Set the path of the rclone executable, look for it again and read its version. A running daemon keeps the previous executable until
+    /// [`RCloneManager::start_daemon`] finds it idle and restarts it
+    /// # Arguments
+    /// * `exe` - Path of the rclone executable or of the directory containing it. If None, look in the sidecar directory and on the PATH
+    pub fn set_exe(&mut self, exe: Option<String>) {
+        self.exe = RCloneManager::find_exe(&exe);
+        self.configured_exe = exe;
+        self.version = self.detect_version().ok();
+        if self.daemon.is_some() {
+            self.restart_daemon = true;
+        }
+    }
+
+    /// Get the path of the rclone executable commands are run with, if one was found
+    pub fn exe_path(&self) -> Option<String> {
+        return self.exe.as_ref().map(|exe| exe.to_string_lossy().to_string());
+    }
+
+    /// Get the path of the rclone executable set by the user
+    pub fn configured_exe(&self) -> Option<String> {
+        return self.configured_exe.clone();
     }
 
     /// Check if the required rclone executable exists
     pub fn rclone_exe_exists(&self) -> bool{
-        let full_path = self.custom_path.clone() + &self.exe.clone();
-        let res = metadata(full_path);
-        if res.is_ok() {
-            return res.unwrap().is_file();
+        return self.exe.as_ref().map_or(false, |exe| exe.is_file());
+    }
+
+    /// Run `rclone version` and parse it
+    pub fn detect_version(&self) -> Result<RCloneVersion, IOError>{
+        let output = self.command()
+                    .arg("version")
+                    .output()?;
+        if !output.status.success() {
+            return Err(IOError::new(IOErrorKind::Other, String::from_utf8_lossy(&output.stderr).trim_end().to_string()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        return RCloneVersion::parse(&stdout).ok_or(IOError::new(IOErrorKind::InvalidData, format!("Unknown rclone version: {}", stdout.trim_end())));
+    }
+
+    /// Get the version of the rclone executable, None if it couldn't be run
+    pub fn version(&self) -> Option<RCloneVersion> {
+        return self.version;
+    }
+
+    /// Check that rclone could be run and isn't older than [`MIN_VERSION`]. The error explains what is wrong so it can be shown as is
+    pub fn check_version(&self) -> Result<RCloneVersion, IOError> {
+        let version = match self.version {
+            Some(version) => version,
+            None if self.rclone_exe_exists() => return Err(IOError::new(IOErrorKind::InvalidData, "rclone was found but its version could not be read")),
+            None => return Err(IOError::new(IOErrorKind::NotFound, "rclone was not found. Set its path or install it on the PATH"))
+        };
+        if version < MIN_VERSION {
+            return Err(IOError::new(IOErrorKind::Unsupported, format!("rclone {} is too old, Remit needs {} or newer",
+                                                                  version.to_string(), MIN_VERSION.to_string())));
+        }
+        return Ok(version);
+    }
+
+    /// Check if the rclone executable can run a feature. A version that couldn't be read is assumed to support everything, so
+    /// the command runs and rclone reports the problem
+    /// # Arguments
+    /// * `feature` - Feature to check
+    pub fn supports(&self, feature: RCloneFeature) -> bool {
+        return self.version.map_or(true, |version| version.supports(feature));
+    }
+
+    /// Error if the rclone executable can't run a feature
+    /// # Arguments
+    /// * `feature` - Feature needed
+    pub fn require(&self, feature: RCloneFeature) -> Result<(), IOError> {
+        if self.supports(feature) {
+            return Ok(());
         }
-        return false;
+        return Err(IOError::new(IOErrorKind::Unsupported, format!("{} needs rclone {} or newer, found {}", feature.to_string(),
+                                                              feature.min_version().to_string(), self.version.unwrap().to_string())));
+    }
+
+    /// Get the program rclone commands are run with. If rclone wasn't found it is run by name, so spawning it fails with
+    /// NotFound unless it was installed since
+    fn program(&self) -> PathBuf {
+        return self.exe.clone().unwrap_or(PathBuf::from(RCLONE_NAME));
+    }
+
+    /// Create a command running the rclone executable without a console window
+    fn command(&self) -> Command {
+        let mut command = Command::new(self.program());
+        hide_window(&mut command);
+        return command;
     }
 
-    /// Load all rclone configurations
+    /// Load all rclone configurations from `rclone config dump`. The previously loaded configurations are only replaced if
+    /// the output could be parsed
     pub fn load_configs(&mut self) -> Result<(), IOError>{
-        let output = Command::new(self.exe.clone())
+        let output = self.command()
                     .arg("config")
-                    .arg("show")
-                    .creation_flags(CREATE_NO_WINDOW).output()?;
-        let output_str = String::from_utf8(output.stdout).or(Err(IOError::new(IOErrorKind::UnexpectedEof, "Error converting stdout")))?;
+                    .arg("dump")
+                    .output()?;
+        if !output.status.success() {
+            return Err(IOError::new(IOErrorKind::Other, format!("rclone config dump failed: {}", String::from_utf8_lossy(&output.stderr))));
+        }
+        let output_str = String::from_utf8(output.stdout).or(Err(IOError::new(IOErrorKind::InvalidData, "Error converting stdout")))?;
+        let configs = RCloneConfig::parse_dump(&output_str)?;
         self.configs.clear();
-        for config in output_str.split("\n") {
-            let c = RCloneConfig::parse_config(config.to_string())?;
-            self.configs.insert(c.name.clone(), c.clone());
+        for c in configs {
+            self.configs.insert(c.name.clone(), c);
         }
         return Ok(());
     }
 
+    /// Get a copy of a loaded configuration by name
+    /// # Arguments
+    /// * `name` - Name of the configuration
+    pub fn get_config(&self, name: &String) -> Option<RCloneConfig> {
+        return self.configs.get(name).cloned();
+    }
+
     /// Get all the current rclone configuration names
     /// 
     /// This method only returns the configuration names not their contents
@@ -158,11 +729,10 @@ impl RCloneManager {
     /// * `name` - Name of configuration to delete
     pub fn delete_config(&mut self, name: String) -> Result<std::process::Output, std::io::Error>{
         if self.config_exists(&name) {
-            let output = Command::new(self.exe.clone())
+            let output = self.command()
                     .arg("config")
                     .arg("delete")
                     .arg(name.clone())
-                    .creation_flags(CREATE_NO_WINDOW)
                     .output();
             if output.is_ok() {
                 self.configs.remove(&name);
@@ -183,31 +753,258 @@ impl RCloneManager {
     /// * `pem_file` - Location and name of PEM file. **Currently not used**
     pub fn create_sftp_config(&mut self, name: String, username: String, 
                                 host: String, password: Option<String>,
-                                pem_file: Option<String>) -> Result<std::process::Output, std::io::Error>{
+                                pem_file: Option<String>) -> Result<(), IOError>{
+        let mut options: BTreeMap<String, String> = BTreeMap::new();
+        options.insert("host".to_string(), host);
+        options.insert("user".to_string(), username);
+        if password.is_some() {
+            options.insert("pass".to_string(), password.unwrap());
+        }
+        if pem_file.is_some() {
+            options.insert("key_file".to_string(), pem_file.unwrap());
+        }
+        return self.create_config(name, Backend::Sftp, options);
+    }
+
+    /// Create an rclone config of any supported backend. Passwords are obscured by rclone
+    /// 
+    /// The configuration is created through the daemon, which is started if needed. Options like passwords and access keys are
+    /// sent in the body of the rc request, so unlike arguments of `rclone config create` they can't be read from the process list.
+    /// rclone doesn't save options given to `config create` in its environment, so they can't be passed that way either
+    /// 
+    /// If a configuration already exists with that name, an option isn't one of the backend's options or rclone fails, returns
+    /// an error
+    /// # Arguments
+    /// * `name` - Name of the configuration
+    /// * `backend` - Backend of the configuration
+    /// * `options` - Values of the backend's options by name, see [`Backend::options`]
+    pub fn create_config(&mut self, name: String, backend: Backend, options: BTreeMap<String, String>) -> Result<(), IOError>{
         if self.config_exists(&name) {
             return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "Config already exists"));
         }
-        let mut command = Command::new(self.exe.clone());
-        command.arg("config")
-                .arg("create")
-                .arg(name)
-                .arg("sftp")
-                .arg("host")
-                .arg(host)
-                .arg("user")
-                .arg(username)
-                .creation_flags(CREATE_NO_WINDOW);
-        if password.is_some() {
-            command.arg("pass")
-                    .arg(format!("{}", password.unwrap()));
+        let allowed = backend.options();
+        if let Some(option) = options.keys().find(|o| !allowed.contains(&o.as_str())) {
+            return Err(IOError::new(IOErrorKind::InvalidInput, format!("{} is not an option of the {} backend", option, backend)));
         }
-        if pem_file.is_some() {
-            command.arg("key-file")
-                    .arg(format!("\"{}\"", pem_file.unwrap()));
+        if let Some(option) = backend.required_options().into_iter().find(|o| options.get(*o).map_or(true, |v| v.len() == 0)) {
+            return Err(IOError::new(IOErrorKind::InvalidInput, format!("The {} backend needs the {} option", backend, option)));
         }
-        command.arg("--non-interactive");
+        self.start_daemon()?;
+        let non_interactive = self.supports(RCloneFeature::NonInteractiveConfig);
+        self.daemon()?.create_config(&name, &backend.to_string(), &options, non_interactive)?;
+        // reload after creating so the new configuration can be chosen
         self.load_configs()?;
-        return command.output();
+        return Ok(());
+    }
+
+    /// Get the backend of the chosen configuration. None if no configuration is chosen or its backend isn't supported
+    pub fn current_backend(&self) -> Option<Backend> {
+        return self.configs.get(&self.chosen_config).and_then(|c| c.backend());
+    }
+
+    /// Run an rclone command that changes the remote and return an error with rclone's output if it fails
+    /// # Arguments
+    /// * `args` - rclone arguments
+    fn run_remote_command(&self, args: Vec<String>) -> Result<(), IOError>{
+        println!("rclone {}", args.join(" "));
+        let output = self.command()
+                                    .args(args)
+                                    .output()?;
+        if !output.status.success() {
+            return Err(IOError::new(IOErrorKind::Other, String::from_utf8_lossy(&output.stderr).trim_end().to_string()));
+        }
+        return Ok(());
+    }
+
+    /// List a remote directory of the chosen configuration with `rclone lsjson`. Used for backends without an ssh session. The
+    /// listing includes . and .. like an ssh listing
+    /// # Arguments
+    /// * `remote_dir` - Remote directory
+    pub fn list_remote_dir(&self, remote_dir: &Remit::SystemPath) -> Result<BTreeMap<String, Remit::RemitFile>, IOError>{
+        let output = self.command()
+                                    .arg("lsjson")
+                                    .arg(self.remote_fs(remote_dir))
+                                    .output()?;
+        if !output.status.success() {
+            return Err(IOError::new(IOErrorKind::Other, String::from_utf8_lossy(&output.stderr).trim_end().to_string()));
+        }
+        let entries: Vec<ListEntry> = serde_json::from_slice(&output.stdout)
+            .map_err(|e| IOError::new(IOErrorKind::InvalidData, format!("Error parsing rclone lsjson: {}", e)))?;
+        let mut files: BTreeMap<String, Remit::RemitFile> = BTreeMap::new();
+        for name in [".", ".."] {
+            files.insert(name.to_string(), Remit::RemitFile::new_populated(name.to_string(), None, Some(Remit::FileType::TypeDirectory)));
+        }
+        for entry in entries {
+            let file_type = if entry.is_dir { Remit::FileType::TypeDirectory } else { Remit::FileType::TypeFile };
+            let size = if entry.size >= 0 { Some(entry.size as u64) } else { None };
+            files.insert(entry.name.clone(), Remit::RemitFile::new_populated(entry.name, size, Some(file_type)));
+        }
+        return Ok(files);
+    }
+
+    /// Check if a remote path of the chosen configuration is a directory. Uses `rclone lsjson --stat` where rclone has it, otherwise
+    /// lists the path, which only works for directories. Errors if the path doesn't exist
+    /// # Arguments
+    /// * `path` - Absolute remote path
+    pub fn is_remote_dir(&self, path: &Remit::SystemPath) -> Result<bool, IOError>{
+        if !self.supports(RCloneFeature::StatJson) {
+            return self.list_remote_dir(path).map(|_| true);
+        }
+        let output = self.command()
+                                    .arg("lsjson")
+                                    .arg("--stat")
+                                    .arg(self.remote_fs(path))
+                                    .output()?;
+        if !output.status.success() {
+            return Err(IOError::new(IOErrorKind::NotFound, String::from_utf8_lossy(&output.stderr).trim_end().to_string()));
+        }
+        let entry: ListEntry = serde_json::from_slice(&output.stdout)
+            .map_err(|e| IOError::new(IOErrorKind::InvalidData, format!("Error parsing rclone lsjson: {}", e)))?;
+        return Ok(entry.is_dir);
+    }
+
+    /// Create a remote directory of the chosen configuration with `rclone mkdir`. Parent directories are created as needed
+    /// # Arguments
+    /// * `path` - Absolute remote path of the directory
+    pub fn make_remote_dir(&self, path: &Remit::SystemPath) -> Result<(), IOError>{
+        return self.run_remote_command(vec!["mkdir".to_string(), self.remote_fs(path)]);
+    }
+
+    /// Create an empty remote file of the chosen configuration, or update its modification time, with `rclone touch`
+    /// # Arguments
+    /// * `path` - Absolute remote path of the file
+    pub fn touch_remote_file(&self, path: &Remit::SystemPath) -> Result<(), IOError>{
+        return self.run_remote_command(vec!["touch".to_string(), self.remote_fs(path)]);
+    }
+
+    /// Delete a remote file of the chosen configuration with `rclone deletefile`, or a directory and its contents with `rclone purge`
+    /// # Arguments
+    /// * `path` - Absolute remote path
+    /// * `recursive` - True to delete a directory
+    pub fn delete_remote_path(&self, path: &Remit::SystemPath, recursive: bool) -> Result<(), IOError>{
+        let command = if recursive { "purge" } else { "deletefile" };
+        return self.run_remote_command(vec![command.to_string(), self.remote_fs(path)]);
+    }
+
+    /// Move or rename a remote file or directory of the chosen configuration with `rclone moveto`
+    /// # Arguments
+    /// * `from` - Absolute remote path to move
+    /// * `to` - Absolute remote path to move to
+    pub fn move_remote_path(&self, from: &Remit::SystemPath, to: &Remit::SystemPath) -> Result<(), IOError>{
+        return self.run_remote_command(vec!["moveto".to_string(), self.remote_fs(from), self.remote_fs(to)]);
+    }
+
+    /// Compute the hashes of a local file or every file in a local directory using `rclone hashsum`. Returns the raw output, one
+    /// `hash  path` line per file with paths relative to the directory
+    /// # Arguments
+    /// * `algorithm` - Name of the hash e.g. sha256 or md5
+    /// * `local_path` - Path to the file or directory relative to the current directory
+    pub fn hashsum(&mut self, algorithm: &String, local_path: &Remit::SystemPath) -> Result<String, IOError>{
+        let output = self.command()
+                                    .arg("hashsum")
+                                    .arg(algorithm)
+                                    .arg(local_path.get_windows_path_local())
+                                    .output()?;
+        if !output.status.success() {
+            return Err(IOError::new(IOErrorKind::Other, String::from_utf8_lossy(&output.stderr).to_string()));
+        }
+        return String::from_utf8(output.stdout).or(Err(IOError::new(IOErrorKind::InvalidData, "Error converting stdout")));
+    }
+
+    /// Turn verification of copied files on or off
+    /// # Arguments
+    /// * `enabled` - If true, copies are checked against the source after each transfer
+    /// * `retries` - Number of times a file that doesn't match is copied again before the transfer fails
+    pub fn set_verification(&mut self, enabled: bool, retries: u32) {
+        self.verify = enabled;
+        self.verify_retries = retries;
+    }
+
+    /// Get whether copied files are verified and how many times a mismatching file is copied again
+    pub fn verification(&self) -> (bool, u32) {
+        return (self.verify, self.verify_retries);
+    }
+
+    /// Check that a file is identical in two directories using `rclone check`. Hashes are compared when both sides support a common
+    /// hash, otherwise only sizes. Returns false if the file differs or is missing from either side
+    /// # Arguments
+    /// * `src_fs` - rclone remote string of the first directory
+    /// * `dst_fs` - rclone remote string of the second directory
+    /// * `filename` - Name of the file in both directories
+    pub fn check_file(&self, src_fs: &String, dst_fs: &String, filename: &String) -> Result<bool, IOError>{
+        return RCloneManager::check_result(self.check_command(src_fs, dst_fs, filename).output()?);
+    }
+
+    /// Build the `rclone check` command used by [`RCloneManager::check_file`], so it can be run without holding the manager.
+    /// Read its output with [`RCloneManager::check_result`]
+    /// # Arguments
+    /// * `src_fs` - rclone remote string of the first directory
+    /// * `dst_fs` - rclone remote string of the second directory
+    /// * `filename` - Name of the file in both directories
+    pub fn check_command(&self, src_fs: &String, dst_fs: &String, filename: &String) -> Command {
+        let mut command = self.command();
+        command.arg("check")
+               .arg(src_fs)
+               .arg(dst_fs)
+               .arg("--include")
+               .arg(RCloneManager::exact_file_pattern(filename))
+               .args(self.limits.args());
+        return command;
+    }
+
+    /// Get an `--include` pattern matching only one file at the top of a directory, with glob characters in its name escaped
+    /// # Arguments
+    /// * `filename` - Name of the file
+    fn exact_file_pattern(filename: &String) -> String {
+        let mut pattern = "/".to_string();
+        for c in filename.chars() {
+            if "*?[]{}\\".contains(c) {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        return pattern;
+    }
+
+    /// Get whether a finished `rclone check` found the file identical on both sides
+    /// # Arguments
+    /// * `output` - Output of a command built by [`RCloneManager::check_command`]
+    pub fn check_result(output: std::process::Output) -> Result<bool, IOError>{
+        if output.status.success() {
+            return Ok(true);
+        }
+        let log = String::from_utf8_lossy(&output.stderr).to_string();
+        if log.contains("differences found") {
+            return Ok(false);
+        }
+        return Err(IOError::new(IOErrorKind::Other, log));
+    }
+
+    /// Run `rclone sync` for one file and, if verification is on, check the copy and sync again while it differs
+    /// # Arguments
+    /// * `source` - rclone remote string of the file to copy
+    /// * `destination` - rclone remote string of the directory to copy into
+    /// * `source_dir` - rclone remote string of the directory containing the file, used to verify the copy
+    /// * `filename` - Name of the file
+    fn sync_verified(&mut self, source: &String, destination: &String, source_dir: &String, filename: &String) -> Result<std::process::ExitStatus, IOError>{
+        let mut attempt = 0u32;
+        loop {
+            println!("rclone.exe sync {} {}", source, destination);
+            let output = self.command()
+                                        .arg("sync")
+                                        .arg(source)
+                                        .arg(destination)
+                                        .args(self.limits.args())
+                                        .output()?;
+            if !output.status.success() || !self.verify || self.check_file(source_dir, destination, filename)? {
+                return Ok(output.status);
+            }
+            if attempt >= self.verify_retries {
+                return Err(IOError::new(IOErrorKind::InvalidData, format!("{} does not match its source after {} attempts", filename, attempt + 1)));
+            }
+            attempt += 1;
+            println!("{} does not match its source, copying again", filename);
+        }
     }
 
     /// Attempt to download a remote file using directory and filename. Throws an error if rsync exits improperly.
@@ -220,20 +1017,425 @@ impl RCloneManager {
     /// * `remote_dir` - A system path that should point to the file's location on the remote server
     /// * `filename` - Name of file to upload
     pub fn download_remote_file(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, filename: String) -> Result<std::process::ExitStatus, IOError>{
-        let mut local_path = local_dir.clone();
         let mut remote_path = remote_dir.clone();
+        remote_path.pushd(filename.clone());
+        let local_fs = self.local_fs(&local_dir);
+        let remote_fs = self.remote_fs(&remote_dir);
+        let source = self.remote_fs(&remote_path);
+        return self.sync_verified(&source, &local_fs, &remote_fs, &filename);
+    }
+
+    /// Start the rclone daemon used for async transfers. Does nothing if it is already running, unless it is idle and has to be
+    /// restarted to pick up a new bandwidth timetable
+    pub fn start_daemon(&mut self) -> Result<(), IOError>{
+        if self.restart_daemon && self.daemon_running() && self.daemon()?.active_transfers().unwrap_or(1) == 0 {
+            self.stop_daemon();
+        }
+        if self.daemon_running() {
+            return Ok(());
+        }
+        self.daemon = Some(Remit::RCloneDaemon::start(&self.program(), &self.limits.args())?);
+        self.restart_daemon = false;
+        return Ok(());
+    }
+
+    /// Get the bandwidth and concurrency limits
+    pub fn limits(&self) -> Remit::TransferLimits {
+        return self.limits.clone();
+    }
+
+    /// Change the bandwidth and concurrency limits. One-shot commands use them straight away. A running daemon is updated through
+    /// the rc api: a plain bandwidth limit applies to running jobs, concurrency only to jobs started afterwards. A bandwidth
+    /// timetable is only read by rclone at startup, so the daemon is restarted as soon as no transfers are running. The daemon is
+    /// also restarted when replacing a timetable, which would otherwise override the new limit at its next time slot. The limits
+    /// are only kept if the daemon accepted them
+    /// # Arguments
+    /// * `limits` - New limits
+    pub fn set_limits(&mut self, limits: Remit::TransferLimits) -> Result<(), IOError>{
+        limits.validate()?;
+        let old = self.limits.clone();
+        if !self.daemon_running() {
+            self.limits = limits;
+            return Ok(());
+        }
+        let bwlimit_changed = limits.bwlimit != old.bwlimit;
+        let restart = {
+            let daemon = self.daemon()?;
+            daemon.set_concurrency(limits.transfers, limits.checkers)?;
+            if bwlimit_changed && !limits.is_timetable() {
+                let rate = if limits.bwlimit.trim().len() > 0 { limits.bwlimit.trim().to_string() } else { "off".to_string() };
+                if let Err(e) = daemon.set_bwlimit(&rate) {
+                    let _r = daemon.set_concurrency(old.transfers, old.checkers);
+                    return Err(e);
+                }
+            }
+            bwlimit_changed && (limits.is_timetable() || old.is_timetable())
+        };
+        self.limits = limits;
+        if !restart {
+            return Ok(());
+        }
+        if self.daemon()?.active_transfers().unwrap_or(1) == 0 {
+            self.stop_daemon();
+            if let Err(e) = self.start_daemon() {
+                self.limits = old;
+                return Err(e);
+            }
+            return Ok(());
+        }
+        println!("bandwidth limit will be fully applied once the running transfers finish");
+        self.restart_daemon = true;
+        return Ok(());
+    }
+
+    /// Shut the rclone daemon down. Running jobs are stopped with it
+    pub fn stop_daemon(&mut self) {
+        if let Some(mut daemon) = self.daemon.take() {
+            daemon.stop();
+        }
+    }
+
+    /// Check if the rclone daemon has been started and is still running
+    pub fn daemon_running(&mut self) -> bool {
+        return self.daemon.as_mut().map(|daemon| daemon.is_running()).unwrap_or(false);
+    }
+
+    /// Get a client for the running daemon, so jobs can be followed without holding the manager's lock
+    pub fn daemon_client(&mut self) -> Result<Remit::RcClient, IOError>{
+        return Ok(self.daemon()?.client());
+    }
+
+    /// Get the running daemon or an error if it hasn't been started
+    fn daemon(&mut self) -> Result<&Remit::RCloneDaemon, IOError>{
+        if !self.daemon_running() {
+            return Err(IOError::new(IOErrorKind::NotConnected, "rclone daemon is not running"));
+        }
+        return Ok(self.daemon.as_ref().unwrap());
+    }
+
+    /// Get the rclone remote string of a local directory. Local paths are relative to the current directory unless a custom path is set
+    /// # Arguments
+    /// * `local_dir` - Local directory
+    pub fn local_fs(&self, local_dir: &Remit::SystemPath) -> String {
+        let mut local_path = local_dir.clone();
         if self.custom_path.len() > 0 {
             local_path.prepd(self.custom_path.clone());
         }
-        remote_path.pushd(filename.clone());
-        println!("rclone.exe sync {}:{} {}", self.chosen_config, remote_path.get_path(), local_path.get_windows_path_local(), );
-        let output = Command::new(self.exe.clone())
-                                    .arg("sync")
-                                    .arg(format!("{}:{}", self.chosen_config, remote_path.get_path()))
-                                    .arg(format!("{}", local_path.get_windows_path_local()))
-                                    .creation_flags(CREATE_NO_WINDOW)
+        return local_path.get_windows_path_local();
+    }
+
+    /// Get the rclone remote string of a remote directory using the chosen configuration e.g. config:/home/user
+    /// # Arguments
+    /// * `remote_dir` - Remote directory
+    pub fn remote_fs(&self, remote_dir: &Remit::SystemPath) -> String {
+        return format!("{}:{}", self.chosen_config, remote_dir.get_path());
+    }
+
+    /// Start copying a file between two rclone remote strings through the daemon and return the job id. The file keeps its name
+    /// # Arguments
+    /// * `src_fs` - Directory containing the file. See [`RCloneManager::local_fs`] and [`RCloneManager::remote_fs`]
+    /// * `dst_fs` - Directory to copy the file into
+    /// * `filename` - Name of the file
+    pub fn start_copy_job(&mut self, src_fs: &String, dst_fs: &String, filename: &String) -> Result<u64, IOError>{
+        return self.daemon()?.copy_file(src_fs, filename, dst_fs, filename);
+    }
+
+    /// Start copying every file of a remote directory into a local directory through the daemon and return the job id. Files that
+    /// only exist locally are kept
+    /// # Arguments
+    /// * `local_dir` - Local directory to copy into. Created if needed
+    /// * `remote_dir` - Remote directory to copy
+    /// * `filter` - Depth and patterns limiting which files are copied
+    pub fn start_directory_download_job(&mut self, local_dir: &Remit::SystemPath, remote_dir: &Remit::SystemPath, filter: &Remit::DirectoryFilter) -> Result<u64, IOError>{
+        let local_fs = self.local_fs(local_dir);
+        let remote_fs = self.remote_fs(remote_dir);
+        let rules = filter.filter_rules(self.ignore_filter(local_dir));
+        return self.daemon()?.copy_dir(&remote_fs, &local_fs, &rules, filter.max_depth);
+    }
+
+    /// Start downloading a remote file through the daemon and return the job id. Same paths as [`RCloneManager::download_remote_file`]
+    /// # Arguments
+    /// * `local_dir` - A system path object that is set to point where the file should be created
+    /// * `remote_dir` - A system path that should point to the file's location on the remote server
+    /// * `filename` - Name of file to download
+    pub fn start_download_job(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, filename: String) -> Result<u64, IOError>{
+        let local_fs = self.local_fs(&local_dir);
+        let remote_fs = self.remote_fs(&remote_dir);
+        return self.start_copy_job(&remote_fs, &local_fs, &filename);
+    }
+
+    /// Start uploading a local file through the daemon and return the job id. Same paths as [`RCloneManager::upload_local_file`]
+    /// # Arguments
+    /// * `local_dir` - A system path object that is set to point where the file is located
+    /// * `remote_dir` - A system path that should point to the file's location on the remote server
+    /// * `filename` - Name of file to upload
+    pub fn start_upload_job(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, filename: String) -> Result<u64, IOError>{
+        let local_fs = self.local_fs(&local_dir);
+        let remote_fs = self.remote_fs(&remote_dir);
+        return self.start_copy_job(&local_fs, &remote_fs, &filename);
+    }
+
+    /// Start syncing a local directory to a remote directory, or the other way round, through the daemon and return the job id
+    /// # Arguments
+    /// * `local_dir` - Local directory
+    /// * `remote_dir` - Remote directory
+    /// * `upload` - If true the remote directory is made identical to the local one, otherwise the local one to the remote one
+    pub fn start_sync_job(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, upload: bool) -> Result<u64, IOError>{
+        let local_fs = self.local_fs(&local_dir);
+        let remote_fs = self.remote_fs(&remote_dir);
+        let rules = self.ignore_filter(&local_dir);
+        if upload {
+            return self.daemon()?.sync(&local_fs, &remote_fs, &rules);
+        }
+        return self.daemon()?.sync(&remote_fs, &local_fs, &rules);
+    }
+
+    /// Set the gitignore style patterns of the configuration. They apply to every tracked directory and directory transfer along
+    /// with the `.remitignore` files found in the directories
+    /// # Arguments
+    /// * `patterns` - One pattern per entry
+    pub fn set_ignore_patterns(&mut self, patterns: Vec<String>) {
+        self.ignore_patterns = patterns;
+    }
+
+    /// Get the gitignore style patterns of the configuration
+    pub fn ignore_patterns(&self) -> Vec<String> {
+        return self.ignore_patterns.clone();
+    }
+
+    /// Check if a file in a tracked directory is ignored by the configuration's patterns or a `.remitignore` file
+    /// # Arguments
+    /// * `root` - Tracked local directory
+    /// * `path` - Path of the file relative to root, separated by /
+    pub fn is_ignored(&self, root: &Remit::SystemPath, path: &String) -> bool {
+        let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let root = PathBuf::from(self.local_fs(root));
+        return Remit::IgnoreRules::load(&root, &self.ignore_patterns, dir).is_ignored(path, false);
+    }
+
+    /// Get the rclone filter rules for transferring a local directory. Directories in the local mirror also use the `.remitignore`
+    /// files of their parents in the mirror, other directories only their own and those below them
+    /// # Arguments
+    /// * `local_dir` - Local directory being transferred
+    pub fn ignore_filter(&self, local_dir: &Remit::SystemPath) -> Vec<String> {
+        let mut mirror = Remit::SystemPath::new();
+        mirror.set_win_path(format!("{}\\.remote", self.chosen_config));
+        let mirror_path = mirror.get_windows_path_local();
+        let local_path = local_dir.get_windows_path_local();
+        let (root, dir) = if local_path == mirror_path {
+            (mirror, String::new())
+        } else if local_path.starts_with(&(mirror_path.clone() + "\\")) {
+            (mirror, local_path[mirror_path.len() + 1..].replace("\\", "/"))
+        } else {
+            (local_dir.clone(), String::new())
+        };
+        let root = PathBuf::from(self.local_fs(&root));
+        let mut rules = Remit::IgnoreRules::load(&root, &self.ignore_patterns, &dir);
+        rules.load_below(&root, &dir);
+        return rules.filter_rules(&dir);
+    }
+
+    /// Build an `rclone bisync` command between a local and a remote directory without running it, so it can be run without
+    /// holding the manager. The baseline listings of the pair are kept in workdir. Errors if rclone is too old to have bisync
+    /// # Arguments
+    /// * `local_dir` - Local directory, path1 of the sync
+    /// * `remote_dir` - Remote directory, path2 of the sync
+    /// * `workdir` - Directory holding the baseline of this pair
+    /// * `resync` - Build a new baseline. Required for the first run and to recover from a failed run
+    pub fn bisync_command(&self, local_dir: &Remit::SystemPath, remote_dir: &Remit::SystemPath, workdir: &Remit::SystemPath, resync: bool) -> Result<Command, IOError> {
+        self.require(RCloneFeature::Bisync)?;
+        let mut command = self.command();
+        command.arg("bisync")
+                .arg(self.local_fs(local_dir))
+                .arg(self.remote_fs(remote_dir))
+                .arg("--workdir")
+                .arg(workdir.get_windows_path_local())
+                .arg("--verbose")
+                .args(self.limits.args())
+                .args(self.ignore_filter(local_dir).into_iter().flat_map(|rule| vec!["--filter".to_string(), rule]));
+        if resync {
+            command.arg("--resync");
+        }
+        return Ok(command);
+    }
+
+    /// Get the status of a daemon job
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn job_status(&mut self, id: u64) -> Result<Remit::JobStatus, IOError>{
+        return self.daemon()?.job_status(id);
+    }
+
+    /// Get the transfer statistics of a daemon job
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn job_stats(&mut self, id: u64) -> Result<Remit::TransferStats, IOError>{
+        return self.daemon()?.job_stats(id);
+    }
+
+    /// Watch a daemon job on a new thread, sending its progress to a listener twice a second until it finishes
+    /// 
+    /// The manager is only locked to get the daemon's client, not while waiting on the daemon, so other commands can run during
+    /// the transfer. If the daemon stops or can't be reached the transfer is reported as failed
+    /// # Arguments
+    /// * `manager` - Manager that started the job
+    /// * `id` - Job id
+    /// * `listener` - Receives the progress and the final result
+    pub fn watch_job(manager: &Arc<Mutex<RCloneManager>>, id: u64, listener: Remit::TransferListener) {
+        let manager = manager.clone();
+        spawn(move || {
+            loop {
+                let poll = manager.lock().map_err(|_| IOError::new(IOErrorKind::Other, "rclone manager lock poisoned"))
+                                    .and_then(|mut m| m.daemon_client())
+                                    .and_then(|client| -> Result<(Remit::JobStatus, Remit::TransferStats), IOError> {
+                    return Ok((client.job_status(id)?, client.job_stats(id)?));
+                });
+                match poll {
+                    Ok((status, stats)) => {
+                        listener(Remit::TransferEvent::Progress(Remit::TransferProgress{id: id, source: Remit::TransferSource::Job, bytes: stats.bytes, total_bytes: stats.total_bytes,
+                                                                                        speed: stats.speed, eta: stats.eta}));
+                        if status.finished {
+                            listener(Remit::TransferEvent::Finished(Remit::TransferResult{id: id, source: Remit::TransferSource::Job, success: status.success, error: status.error}));
+                            return;
+                        }
+                    },
+                    Err(e) => {
+                        listener(Remit::TransferEvent::Finished(Remit::TransferResult{id: id, source: Remit::TransferSource::Job, success: false, error: e.to_string()}));
+                        return;
+                    }
+                }
+                sleep(Duration::from_millis(500));
+            }
+        });
+    }
+
+    /// Cancel a daemon job
+    /// # Arguments
+    /// * `id` - Job id
+    pub fn stop_job(&mut self, id: u64) -> Result<(), IOError>{
+        return self.daemon()?.stop_job(id);
+    }
+
+    /// Run an rclone transfer command with `--dry-run` and return the changes it would make. Errors if rclone fails, with the
+    /// messages it logged at error level, or if it is too old to log JSON
+    /// # Arguments
+    /// * `args` - rclone arguments e.g. sync src dst
+    fn plan(&mut self, args: Vec<String>) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        self.require(RCloneFeature::JsonLog)?;
+        let output = self.command()
+                                    .args(args)
+                                    .arg("--dry-run")
+                                    .arg("--use-json-log")
                                     .output()?;
-        return Ok(output.status);
+        return RCloneManager::plan_result(output);
+    }
+
+    /// Get the changes a finished rclone command run with `--dry-run --use-json-log` would have made. Errors with the messages
+    /// rclone logged at error level if it failed
+    /// # Arguments
+    /// * `output` - Output of the rclone command
+    pub fn plan_result(output: std::process::Output) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let log = String::from_utf8_lossy(&output.stderr).to_string();
+        if !output.status.success() {
+            let errors: Vec<String> = log.lines().filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+                                        .filter(|entry| entry.get("level").and_then(|l| l.as_str()) == Some("error"))
+                                        .filter_map(|entry| entry.get("msg").and_then(|m| m.as_str()).map(|m| m.to_string()))
+                                        .collect();
+            let message = if errors.len() > 0 { errors.join("\n") } else { log };
+            return Err(IOError::new(IOErrorKind::Other, message));
+        }
+        return Ok(parse_dry_run(&log));
+    }
+
+    /// Get the changes [`RCloneManager::download_remote_file`] would make without making them
+    /// # Arguments
+    /// * `local_dir` - A system path object that is set to point where the file should be created
+    /// * `remote_dir` - A system path that should point to the file's location on the remote server
+    /// * `filename` - Name of file to download
+    pub fn plan_download(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, filename: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let mut remote_path = remote_dir.clone();
+        remote_path.pushd(filename);
+        let args = vec!["sync".to_string(), self.remote_fs(&remote_path), self.local_fs(&local_dir)];
+        return self.plan(args);
+    }
+
+    /// Get the changes [`RCloneManager::upload_local_file`] would make without making them
+    /// # Arguments
+    /// * `local_dir` - A system path object that is set to point where the file is located
+    /// * `remote_dir` - A system path that should point to the file's location on the remote server
+    /// * `filename` - Name of file to upload
+    pub fn plan_upload(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, filename: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let mut local_path = local_dir.clone();
+        local_path.pushd(filename);
+        let args = vec!["sync".to_string(), self.local_fs(&local_path), self.remote_fs(&remote_dir)];
+        return self.plan(args);
+    }
+
+    /// Get the changes [`RCloneManager::start_download_job`] would make without making them
+    /// # Arguments
+    /// * `local_dir` - A system path object that is set to point where the file should be created
+    /// * `remote_dir` - A system path that should point to the file's location on the remote server
+    /// * `filename` - Name of file to download
+    pub fn plan_download_job(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, filename: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let local_fs = self.local_fs(&local_dir);
+        let remote_fs = self.remote_fs(&remote_dir);
+        return self.plan_copy(&remote_fs, &local_fs, &filename);
+    }
+
+    /// Get the changes [`RCloneManager::start_upload_job`] would make without making them
+    /// # Arguments
+    /// * `local_dir` - A system path object that is set to point where the file is located
+    /// * `remote_dir` - A system path that should point to the file's location on the remote server
+    /// * `filename` - Name of file to upload
+    pub fn plan_upload_job(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, filename: String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let local_fs = self.local_fs(&local_dir);
+        let remote_fs = self.remote_fs(&remote_dir);
+        return self.plan_copy(&local_fs, &remote_fs, &filename);
+    }
+
+    /// Get the changes [`RCloneManager::start_copy_job`] would make without making them. Transfers in the queue are copied the same way
+    /// # Arguments
+    /// * `src_fs` - Directory containing the file
+    /// * `dst_fs` - Directory to copy the file into
+    /// * `filename` - Name of the file
+    pub fn plan_copy(&mut self, src_fs: &String, dst_fs: &String, filename: &String) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let args = vec!["copy".to_string(), src_fs.clone(), dst_fs.clone(), "--include".to_string(), RCloneManager::exact_file_pattern(filename)];
+        return self.plan(args);
+    }
+
+    /// Get the changes [`RCloneManager::start_sync_job`] would make without making them
+    /// # Arguments
+    /// * `local_dir` - Local directory
+    /// * `remote_dir` - Remote directory
+    /// * `upload` - If true the remote directory would be made identical to the local one, otherwise the local one to the remote one
+    pub fn plan_sync_job(&mut self, local_dir: Remit::SystemPath, remote_dir: Remit::SystemPath, upload: bool) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let local_fs = self.local_fs(&local_dir);
+        let remote_fs = self.remote_fs(&remote_dir);
+        let (src_fs, dst_fs) = if upload { (local_fs, remote_fs) } else { (remote_fs, local_fs) };
+        let mut args = vec!["sync".to_string(), src_fs, dst_fs];
+        for rule in self.ignore_filter(&local_dir) {
+            args.push("--filter".to_string());
+            args.push(rule);
+        }
+        return self.plan(args);
+    }
+
+    /// Get the changes [`RCloneManager::start_directory_download_job`] would make without making them
+    /// # Arguments
+    /// * `local_dir` - Local directory to copy into
+    /// * `remote_dir` - Remote directory to copy
+    /// * `filter` - Depth and patterns limiting which files are copied
+    pub fn plan_directory_download(&mut self, local_dir: &Remit::SystemPath, remote_dir: &Remit::SystemPath, filter: &Remit::DirectoryFilter) -> Result<Vec<Remit::PlannedChange>, IOError>{
+        let mut args = vec!["copy".to_string(), self.remote_fs(remote_dir), self.local_fs(local_dir), "--create-empty-src-dirs".to_string()];
+        for rule in filter.filter_rules(self.ignore_filter(local_dir)) {
+            args.push("--filter".to_string());
+            args.push(rule);
+        }
+        if let Some(depth) = filter.max_depth {
+            args.push("--max-depth".to_string());
+            args.push(depth.to_string());
+        }
+        return self.plan(args);
     }
 
     /// Uploads a local file to the remote machine
@@ -244,23 +1446,110 @@ impl RCloneManager {
     /// * `remote_dir` - A system path that should point to the file's location on the remote server
     /// * `filename` - Name of file to upload
     pub fn upload_local_file(&mut self, local_path: Remit::SystemPath, remote_path: Remit::SystemPath, filename: String) -> Result<std::process::ExitStatus, IOError>{
-        let mut local_path = local_path.clone();
-        let remote_path = remote_path.clone();
-        if self.custom_path.len() > 0 {
-            local_path.prepd(self.custom_path.clone());
-        }
-        local_path.pushd(filename.clone());
-        println!("rclone.exe sync {} {}:{}", local_path.get_windows_path_local(), self.chosen_config, remote_path.get_path());
-        let output = Command::new(self.exe.clone())
-                                    .arg("sync")
-                                    .arg(format!("{}", local_path.get_windows_path_local()))
-                                    .arg(format!("{}:{}", self.chosen_config, remote_path.get_path()))
-                                    .creation_flags(CREATE_NO_WINDOW)
-                                    .output()?;
-        return Ok(output.status);
+        let mut local_file = local_path.clone();
+        local_file.pushd(filename.clone());
+        let local_fs = self.local_fs(&local_path);
+        let remote_fs = self.remote_fs(&remote_path);
+        let source = self.local_fs(&local_file);
+        return self.sync_verified(&source, &remote_fs, &local_fs, &filename);
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    /// Build limits with only a bandwidth limit
+    fn bwlimit(rate: &str) -> TransferLimits {
+        return TransferLimits{bwlimit: rate.to_string(), transfers: 0, checkers: 0};
+    }
+
+    #[test]
+    fn validate_accepts_rates_and_timetables() {
+        for rate in ["", "off", "512k", "1.5M", "10", "1G", "512k:1M", "08:00,512k 19:00,off", "Mon-00:00,512 Fri-23:59,10M:off"] {
+            assert!(bwlimit(rate).validate().is_ok(), "{} should be valid", rate);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_rates_and_timetables() {
+        for rate in ["fast", "-1k", "512kb", "24:00,1M", "08:60,1M", "8:5,1M", "Xyz-08:00,1M", "08:00,fast"] {
+            assert!(bwlimit(rate).validate().is_err(), "{} should be invalid", rate);
+        }
+    }
+
+    #[test]
+    fn filter_rules_put_excludes_before_ignore_rules() {
+        let filter = DirectoryFilter{max_depth: None, include: vec!["*.rs".to_string()], exclude: vec!["secret.rs".to_string()]};
+        let rules = filter.filter_rules(vec!["+ secret.rs".to_string(), "- *.swp".to_string()]);
+        assert_eq!(rules, vec!["- secret.rs", "+ secret.rs", "- *.swp", "+ *.rs", "- **"]);
+        assert_eq!(DirectoryFilter::new().filter_rules(vec!["- *.swp".to_string()]), vec!["- *.swp"]);
+    }
+
+    #[test]
+    fn parse_size_reads_plain_and_binary_sizes() {
+        assert_eq!(parse_size("12"), Some(12));
+        assert_eq!(parse_size("1.500Ki"), Some(1536));
+        assert_eq!(parse_size("3Mi"), Some(3 * 1024 * 1024));
+        assert_eq!(parse_size("2KiB"), Some(2048));
+        assert_eq!(parse_size("1Gi"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size("5X"), None);
+        assert_eq!(parse_size(""), None);
+    }
+
+    #[test]
+    fn parse_dry_run_reads_skipped_operations() {
+        let log = [r#"{"level":"notice","msg":"Skipped copy as --dry-run is set (size 1.500Ki)","object":"a.txt"}"#,
+                   r#"{"level":"notice","msg":"Skipped delete as --dry-run is set (size 3)","object":"dir/b.txt"}"#,
+                   r#"{"level":"notice","msg":"Skipped update modification time as --dry-run is set","object":"c.txt"}"#,
+                   r#"{"level":"notice","msg":"Skipped make directory as --dry-run is set","object":"d"}"#,
+                   r#"{"level":"notice","msg":"Skipped remove directory as --dry-run is set","object":"e"}"#].join("\n");
+        let changes = parse_dry_run(&log);
+        let actions: Vec<PlannedAction> = changes.iter().map(|c| c.action).collect();
+        assert_eq!(actions, vec![PlannedAction::Copy, PlannedAction::Delete, PlannedAction::Update, PlannedAction::MakeDir, PlannedAction::RemoveDir]);
+        assert_eq!(changes[0].path, "a.txt");
+        assert_eq!(changes[0].size, Some(1536));
+        assert_eq!(changes[1].path, "dir/b.txt");
+        assert_eq!(changes[1].size, Some(3));
+        assert_eq!(changes[2].size, None);
+    }
+
+    #[test]
+    fn parse_dry_run_ignores_other_lines() {
+        let log = ["2023/01/01 12:00:00 NOTICE: not json",
+                   r#"{"level":"info","msg":"There was nothing to transfer","object":""}"#,
+                   r#"{"level":"error","msg":"Failed to copy: permission denied","object":"a.txt"}"#].join("\n");
+        assert_eq!(parse_dry_run(&log).len(), 0);
+    }
+
+    #[test]
+    fn rclone_version_reads_first_line() {
+        let output = "rclone v1.64.2\n- os/version: debian 12.2 (64 bit)\n- go/version: go1.21.3";
+        assert_eq!(RCloneVersion::parse(output), Some(RCloneVersion{major: 1, minor: 64, patch: 2}));
+        assert_eq!(RCloneVersion::parse(output).unwrap().to_string(), "v1.64.2");
+    }
+
+    #[test]
+    fn rclone_version_reads_suffixes_and_missing_patch() {
+        assert_eq!(RCloneVersion::parse("rclone v1.65.0-beta.7480.4f9f4ff1b\n"), Some(RCloneVersion{major: 1, minor: 65, patch: 0}));
+        assert_eq!(RCloneVersion::parse("rclone 1.53.3-DEV"), Some(RCloneVersion{major: 1, minor: 53, patch: 3}));
+        assert_eq!(RCloneVersion::parse("rclone v1.58"), Some(RCloneVersion{major: 1, minor: 58, patch: 0}));
+    }
+
+    #[test]
+    fn rclone_version_rejects_other_output() {
+        assert_eq!(RCloneVersion::parse(""), None);
+        assert_eq!(RCloneVersion::parse("command not found"), None);
+        assert_eq!(RCloneVersion::parse("rclone v1"), None);
+    }
+
+    #[test]
+    fn rclone_version_compares_features() {
+        let version = RCloneVersion::parse("rclone v1.57.0").unwrap();
+        assert!(version.supports(RCloneFeature::NonInteractiveConfig));
+        assert!(!version.supports(RCloneFeature::Bisync));
+        assert!(RCloneVersion{major: 2, minor: 0, patch: 0}.supports(RCloneFeature::Bisync));
+    }
+}
 
 }
\ No newline at end of file
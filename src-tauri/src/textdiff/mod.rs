@@ -0,0 +1,249 @@
+//! Line based diffing used to show the changes between the local mirror copy of a file and the file on the remote server.
+//! 
+//! The differences are found with the linear space variant of the Myers algorithm, which finds the shortest list of line insertions
+//! and deletions that turns one text into the other. The result is formatted as a unified diff, the same format produced by `diff -u`
+//! and `git diff`, with a configurable number of context lines around each change.
+
+pub mod rustssh {
+
+/// The differences between 2 versions of a file
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+    /// True if either version is binary. No unified diff is produced for binary files
+    pub binary: bool,
+    /// True if both versions have the same content
+    pub identical: bool,
+    /// The unified diff. Empty if the files are binary or identical
+    pub unified: String
+}
+
+/// A single step in turning the old text into the new text
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffOp {
+    /// Line present in both texts. Holds the line index in the old and the new text
+    Equal(usize, usize),
+    /// Line only present in the old text. Holds the line index in the old text
+    Delete(usize),
+    /// Line only present in the new text. Holds the line index in the new text
+    Insert(usize)
+}
+
+/// Find the shortest list of operations that turns the old lines into the new lines. Uses the linear space variant of Myers,
+/// which splits the texts at the middle of the shortest edit path and diffs both halves, so memory stays proportional to the
+/// number of lines however different the texts are
+/// # Arguments
+/// * `old` - Lines of the original text
+/// * `new` - Lines of the changed text
+pub fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    let mut ops: Vec<DiffOp> = Vec::new();
+    diff_range(old, new, 0, 0, &mut ops);
+    return ops;
+}
+
+/// Diff part of the texts and append the operations
+/// # Arguments
+/// * `old` - Lines of the original text in this part
+/// * `new` - Lines of the changed text in this part
+/// * `old_base` - Index of the first old line of this part in the whole old text
+/// * `new_base` - Index of the first new line of this part in the whole new text
+/// * `ops` - Operations found so far
+fn diff_range(old: &[&str], new: &[&str], old_base: usize, new_base: usize, ops: &mut Vec<DiffOp>) {
+    let mut prefix = 0usize;
+    while prefix < old.len() && prefix < new.len() && old[prefix] == new[prefix] {
+        ops.push(DiffOp::Equal(old_base + prefix, new_base + prefix));
+        prefix += 1;
+    }
+    let mut suffix = 0usize;
+    while suffix < old.len() - prefix && suffix < new.len() - prefix && old[old.len() - suffix - 1] == new[new.len() - suffix - 1] {
+        suffix += 1;
+    }
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let old_mid_base = old_base + prefix;
+    let new_mid_base = new_base + prefix;
+
+    if old_mid.len() == 0 {
+        ops.extend((0..new_mid.len()).map(|j| DiffOp::Insert(new_mid_base + j)));
+    } else if new_mid.len() == 0 {
+        ops.extend((0..old_mid.len()).map(|i| DiffOp::Delete(old_mid_base + i)));
+    } else {
+        match middle_snake(old_mid, new_mid) {
+            Some((x, y)) => {
+                diff_range(&old_mid[..x], &new_mid[..y], old_mid_base, new_mid_base, ops);
+                diff_range(&old_mid[x..], &new_mid[y..], old_mid_base + x, new_mid_base + y, ops);
+            },
+            None => {
+                ops.extend((0..old_mid.len()).map(|i| DiffOp::Delete(old_mid_base + i)));
+                ops.extend((0..new_mid.len()).map(|j| DiffOp::Insert(new_mid_base + j)));
+            }
+        }
+    }
+
+    for i in 0..suffix {
+        ops.push(DiffOp::Equal(old_base + old.len() - suffix + i, new_base + new.len() - suffix + i));
+    }
+}
+
+/// Find a point on a shortest edit path by searching forwards from the start and backwards from the end at the same time until the
+/// paths meet. The texts must not share a first or last line, so the point is never the start or the end and both halves are
+/// smaller. Returns None if the texts have no line in common
+/// # Arguments
+/// * `old` - Lines of the original text
+/// * `new` - Lines of the changed text
+fn middle_snake(old: &[&str], new: &[&str]) -> Option<(usize, usize)> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d + 1;
+    let length = (2 * max_d + 3) as usize;
+    // furthest x reached on each diagonal k = x - y, searching forwards and backwards. -1 if not reached yet
+    let mut forward: Vec<isize> = vec![-1; length];
+    let mut backward: Vec<isize> = vec![-1; length];
+    forward[(offset + 1) as usize] = 0;
+    backward[(offset + 1) as usize] = 0;
+    let delta = n - m;
+    // with an odd delta the paths meet while searching forwards, otherwise while searching backwards
+    let odd = delta % 2 != 0;
+    // diagonals that left the edit grid are skipped from then on
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0isize, 0isize, 0isize, 0isize);
+
+    for d in 0..max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let index = (offset + k1) as usize;
+            let mut x = if k1 == -d || (k1 != d && forward[index - 1] < forward[index + 1]) {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let mut y = x - k1;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index] = x;
+            if x > n {
+                k1_end += 2;
+            } else if y > m {
+                k1_start += 2;
+            } else if odd {
+                let back_index = offset + delta - k1;
+                if back_index >= 0 && back_index < length as isize && backward[back_index as usize] != -1 {
+                    if x >= n - backward[back_index as usize] {
+                        return Some((x as usize, y as usize));
+                    }
+                }
+            }
+            k1 += 2;
+        }
+
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let index = (offset + k2) as usize;
+            let mut x = if k2 == -d || (k2 != d && backward[index - 1] < backward[index + 1]) {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            let mut y = x - k2;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index] = x;
+            if x > n {
+                k2_end += 2;
+            } else if y > m {
+                k2_start += 2;
+            } else if !odd {
+                let forward_index = offset + delta - k2;
+                if forward_index >= 0 && forward_index < length as isize && forward[forward_index as usize] != -1 {
+                    let forward_x = forward[forward_index as usize];
+                    let forward_y = forward_x - (forward_index - offset);
+                    if forward_x >= n - x {
+                        return Some((forward_x as usize, forward_y as usize));
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    return None;
+}
+
+/// Produce a unified diff between 2 texts. Returns an empty string if the texts are the same
+/// # Arguments
+/// * `old` - Original text
+/// * `new` - Changed text
+/// * `old_name` - Name shown in the `---` header
+/// * `new_name` - Name shown in the `+++` header
+/// * `context` - Number of unchanged lines shown around each change
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+    if ops.iter().all(|op| matches!(op, DiffOp::Equal(_, _))) {
+        return String::new();
+    }
+
+    // group changes that are close enough to share context into hunks of op indexes
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        if matches!(op, DiffOp::Equal(_, _)) {
+            continue;
+        }
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end))
+        }
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        let (mut old_start, mut new_start) = position(&ops, start, &old_lines, &new_lines);
+        let old_count = ops[start..end].iter().filter(|op| !matches!(op, DiffOp::Insert(_))).count();
+        let new_count = ops[start..end].iter().filter(|op| !matches!(op, DiffOp::Delete(_))).count();
+        // unified diffs count lines from 1, except an empty range which points at the line before it
+        if old_count > 0 {
+            old_start += 1;
+        }
+        if new_count > 0 {
+            new_start += 1;
+        }
+        output += &format!("@@ -{},{} +{},{} @@\n", old_start, old_count, new_start, new_count);
+        for op in &ops[start..end] {
+            match op {
+                DiffOp::Equal(i, _) => output += &format!(" {}\n", old_lines[*i]),
+                DiffOp::Delete(i) => output += &format!("-{}\n", old_lines[*i]),
+                DiffOp::Insert(j) => output += &format!("+{}\n", new_lines[*j])
+            }
+        }
+    }
+    return output;
+}
+
+/// Get the 0 based line position in the old and new text at which an operation happens
+/// # Arguments
+/// * `ops` - All diff operations
+/// * `index` - Index of the operation
+/// * `old_lines` - Lines of the old text
+/// * `new_lines` - Lines of the new text
+fn position(ops: &[DiffOp], index: usize, old_lines: &[&str], new_lines: &[&str]) -> (usize, usize) {
+    let mut old_pos = 0usize;
+    let mut new_pos = 0usize;
+    for op in &ops[..index] {
+        match op {
+            DiffOp::Equal(_, _) => {
+                old_pos += 1;
+                new_pos += 1;
+            },
+            DiffOp::Delete(_) => old_pos += 1,
+            DiffOp::Insert(_) => new_pos += 1
+        }
+    }
+    return (old_pos.min(old_lines.len()), new_pos.min(new_lines.len()));
+}
+
+}
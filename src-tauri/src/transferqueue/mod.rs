@@ -0,0 +1,421 @@
+//! A queue of uploads and downloads run through the rclone daemon. Transfers are added to the queue and started in order of
+//! priority, highest first, with at most a set number running at once. Each transfer can be paused, resumed, reprioritized and
+//! cancelled on its own.
+//!
+//! Transfers store the full rclone remote strings of their source and destination, so they aren't affected by navigating to
+//! another directory or choosing another configuration after they were queued. rclone can't pause a job, so pausing a running
+//! transfer stops its job and resuming it queues the transfer again to start from the beginning.
+//!
+//! When verification is turned on in the rclone manager, each finished transfer is checked against its source. A copy that doesn't
+//! match is queued again until the configured number of retries is used up, then the transfer fails.
+//!
+//! The queue is kept behind its own lock, separate from the rest of the api, and is driven by a thread started with
+//! [`TransferQueue::start`] that polls the running jobs twice a second. Neither lock is held while waiting on the daemon.
+
+pub mod rustssh {
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+use crate::*;
+
+/// Direction of a transfer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferKind {
+    Upload,
+    Download
+}
+
+/// State of a transfer in the queue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferState {
+    /// Waiting for a free slot
+    Queued,
+    Running,
+    /// Won't be started until resumed
+    Paused,
+    Done,
+    Failed,
+    Cancelled
+}
+
+impl Display for TransferKind {
+    /// Write the lowercase name of the direction
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            TransferKind::Upload=> "upload",
+            TransferKind::Download=> "download"
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+impl Display for TransferState {
+    /// Write the lowercase name of the state
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let name = match self {
+            TransferState::Queued=> "queued",
+            TransferState::Running=> "running",
+            TransferState::Paused=> "paused",
+            TransferState::Done=> "done",
+            TransferState::Failed=> "failed",
+            TransferState::Cancelled=> "cancelled"
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+impl TransferState {
+    /// True if the transfer will not change state again
+    pub fn is_finished(&self) -> bool {
+        return *self == TransferState::Done || *self == TransferState::Failed || *self == TransferState::Cancelled;
+    }
+}
+
+/// A file transfer in the queue
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    /// Id of the transfer in the queue. Unlike the daemon job id it stays the same when the transfer is paused and resumed
+    pub id: u64,
+    pub kind: TransferKind,
+    /// rclone remote string of the directory containing the file
+    pub source: String,
+    /// rclone remote string of the directory to copy the file into
+    pub destination: String,
+    pub filename: String,
+    /// Transfers with a higher priority are started first
+    pub priority: i32,
+    pub state: TransferState,
+    /// Daemon job id while running
+    pub job: Option<u64>,
+    /// Error text of a failed transfer
+    pub error: String,
+    pub bytes: u64,
+    pub total_bytes: u64,
+    /// Number of times the transfer was queued again after failing verification
+    pub retries: u32
+}
+
+/// Queue of transfers run through the rclone daemon
+pub struct TransferQueue {
+    transfers: Vec<Transfer>,
+    next_id: u64,
+    /// Maximum number of transfers running at once
+    max_running: usize,
+    rclone_m: Arc<Mutex<Remit::RCloneManager>>,
+    /// Receives the progress and result of every transfer, keyed by the transfer's queue id
+    listener: Option<Remit::TransferListener>
+}
+
+#[allow(dead_code)]
+impl TransferQueue {
+    /// Create an empty queue running 2 transfers at once
+    /// # Arguments
+    /// * `rclone_m` - rclone manager owning the daemon
+    pub fn new(rclone_m: Arc<Mutex<Remit::RCloneManager>>) -> TransferQueue {
+        return TransferQueue{transfers: Vec::new(), next_id: 1, max_running: 2, rclone_m: rclone_m, listener: None};
+    }
+
+    /// Start the thread that starts queued transfers and follows running ones
+    /// # Arguments
+    /// * `queue` - Queue to drive
+    pub fn start(queue: &Arc<Mutex<TransferQueue>>) {
+        let queue = queue.clone();
+        spawn(move || {
+            loop {
+                if TransferQueue::poll(&queue).is_err() {
+                    return;
+                }
+                sleep(Duration::from_millis(500));
+            }
+        });
+    }
+
+    /// Set the listener that receives progress and result events
+    /// # Arguments
+    /// * `listener` - Called with events keyed by queue id
+    pub fn set_listener(&mut self, listener: Remit::TransferListener) {
+        self.listener = Some(listener);
+    }
+
+    /// Set how many transfers can run at once. At least 1
+    /// # Arguments
+    /// * `max_running` - Maximum number of running transfers
+    pub fn set_concurrency(&mut self, max_running: usize) {
+        self.max_running = max_running.max(1);
+    }
+
+    /// Add a transfer to the queue and return its id
+    /// # Arguments
+    /// * `kind` - Upload or download
+    /// * `source` - rclone remote string of the directory containing the file
+    /// * `destination` - rclone remote string of the directory to copy into
+    /// * `filename` - Name of the file
+    /// * `priority` - Higher priorities are started first
+    pub fn add(&mut self, kind: TransferKind, source: String, destination: String, filename: String, priority: i32) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.transfers.push(Transfer{id: id, kind: kind, source: source, destination: destination, filename: filename, priority: priority,
+                                     state: TransferState::Queued, job: None, error: String::new(), bytes: 0, total_bytes: 0, retries: 0});
+        return id;
+    }
+
+    /// Get every transfer in the queue including finished ones, in the order they were added
+    pub fn list(&self) -> Vec<Transfer> {
+        return self.transfers.clone();
+    }
+
+    /// Find a transfer by id
+    fn find(&mut self, id: u64) -> Result<&mut Transfer, IOError> {
+        return self.transfers.iter_mut().find(|t| t.id == id).ok_or(IOError::new(IOErrorKind::NotFound, "Transfer not found"));
+    }
+
+    /// Pause a queued or running transfer. A running transfer's job is stopped after the queue is unlocked
+    /// # Arguments
+    /// * `queue` - Queue containing the transfer
+    /// * `id` - Queue id of the transfer
+    pub fn pause(queue: &Arc<Mutex<TransferQueue>>, id: u64) -> Result<(), IOError> {
+        let (rclone_m, job) = {
+            let mut q = TransferQueue::lock(queue)?;
+            let rclone_m = q.rclone_m.clone();
+            let transfer = q.find(id)?;
+            let mut job = None;
+            match transfer.state {
+                TransferState::Queued => {},
+                TransferState::Running => {
+                    job = transfer.job.take();
+                    transfer.bytes = 0;
+                },
+                _ => return Err(IOError::new(IOErrorKind::InvalidInput, format!("Can't pause a {} transfer", transfer.state)))
+            }
+            transfer.state = TransferState::Paused;
+            (rclone_m, job)
+        };
+        TransferQueue::stop_job(&rclone_m, job);
+        return Ok(());
+    }
+
+    /// Queue a paused transfer again
+    /// # Arguments
+    /// * `id` - Queue id of the transfer
+    pub fn resume(&mut self, id: u64) -> Result<(), IOError> {
+        let transfer = self.find(id)?;
+        if transfer.state != TransferState::Paused {
+            return Err(IOError::new(IOErrorKind::InvalidInput, "Transfer is not paused"));
+        }
+        transfer.state = TransferState::Queued;
+        return Ok(());
+    }
+
+    /// Change the priority of a transfer. Only affects transfers that haven't started
+    /// # Arguments
+    /// * `id` - Queue id of the transfer
+    /// * `priority` - Higher priorities are started first
+    pub fn set_priority(&mut self, id: u64, priority: i32) -> Result<(), IOError> {
+        self.find(id)?.priority = priority;
+        return Ok(());
+    }
+
+    /// Cancel a transfer that hasn't finished. A running transfer's job is stopped after the queue is unlocked
+    /// # Arguments
+    /// * `queue` - Queue containing the transfer
+    /// * `id` - Queue id of the transfer
+    pub fn cancel(queue: &Arc<Mutex<TransferQueue>>, id: u64) -> Result<(), IOError> {
+        let (rclone_m, job, listener) = {
+            let mut q = TransferQueue::lock(queue)?;
+            let rclone_m = q.rclone_m.clone();
+            let listener = q.listener.clone();
+            let transfer = q.find(id)?;
+            if transfer.state.is_finished() {
+                return Err(IOError::new(IOErrorKind::InvalidInput, "Transfer has already finished"));
+            }
+            transfer.state = TransferState::Cancelled;
+            (rclone_m, transfer.job.take(), listener)
+        };
+        TransferQueue::stop_job(&rclone_m, job);
+        if let Some(listener) = listener {
+            let result = Remit::TransferResult{id: id, source: Remit::TransferSource::Queue, success: false, error: "cancelled".to_string()};
+            listener(Remit::TransferEvent::Finished(result));
+        }
+        return Ok(());
+    }
+
+    /// Stop a transfer's daemon job. The rclone manager is only locked to get a client, not while the daemon stops the job
+    /// # Arguments
+    /// * `rclone_m` - Rclone manager running the daemon
+    /// * `job` - Job to stop, if the transfer had one
+    fn stop_job(rclone_m: &Arc<Mutex<Remit::RCloneManager>>, job: Option<u64>) {
+        if let Some(job) = job {
+            let client = rclone_m.lock().unwrap().daemon_client();
+            if let Ok(client) = client {
+                let _r = client.stop_job(job);
+            }
+        }
+    }
+
+    /// Remove finished transfers from the queue
+    pub fn clear_finished(&mut self) {
+        self.transfers.retain(|t| !t.state.is_finished());
+    }
+
+    /// Lock a queue, failing if a thread panicked while holding it
+    fn lock(queue: &Arc<Mutex<TransferQueue>>) -> Result<MutexGuard<'_, TransferQueue>, IOError> {
+        return queue.lock().map_err(|_| IOError::new(IOErrorKind::Other, "transfer queue lock poisoned"));
+    }
+
+    /// Update the running transfers from the daemon and start queued transfers while there are free slots. Errors only if the
+    /// queue lock is poisoned
+    /// 
+    /// Neither the queue nor the rclone manager is locked while waiting on the daemon or on a verification check, so the queue can
+    /// be listed and changed during a slow poll. The running jobs are read under the queue lock and polled without it, then the
+    /// results are applied under the lock again. Transfers paused or cancelled in the meantime are left alone, and a job started
+    /// for a transfer that was paused or cancelled while starting is stopped again
+    /// # Arguments
+    /// * `queue` - Queue to update
+    pub fn poll(queue: &Arc<Mutex<TransferQueue>>) -> Result<(), IOError> {
+        let (rclone_m, running, wants_slot) = {
+            let q = TransferQueue::lock(queue)?;
+            let running: Vec<Transfer> = q.transfers.iter().filter(|t| t.state == TransferState::Running).cloned().collect();
+            let queued = q.transfers.iter().any(|t| t.state == TransferState::Queued);
+            let wants_slot = queued && running.len() < q.max_running;
+            (q.rclone_m.clone(), running, wants_slot)
+        };
+        if running.len() == 0 && !wants_slot {
+            return Ok(());
+        }
+
+        let (client, verify, max_retries) = {
+            let mut rclone = rclone_m.lock().unwrap();
+            if wants_slot {
+                if let Err(e) = rclone.start_daemon() {
+                    println!("could not start the rclone daemon: {}", e);
+                }
+            }
+            let (verify, max_retries) = rclone.verification();
+            (rclone.daemon_client(), verify, max_retries)
+        };
+
+        let mut updates = Vec::new();
+        for transfer in running {
+            let job = transfer.job.unwrap_or(0);
+            let poll = match &client {
+                Ok(client) => client.job_status(job).map(|status| (status, client.job_stats(job).ok())),
+                Err(e) => Err(IOError::new(e.kind(), e.to_string()))
+            };
+            let mut check = None;
+            if let Ok((status, _)) = &poll {
+                if status.finished && status.success && verify {
+                    // only build the command under the lock, the check itself can take as long as reading the file
+                    let mut command = rclone_m.lock().unwrap().check_command(&transfer.source, &transfer.destination, &transfer.filename);
+                    check = Some(command.output().map_err(IOError::from).and_then(Remit::RCloneManager::check_result));
+                }
+            }
+            updates.push((transfer.id, job, poll, check));
+        }
+
+        let mut events = Vec::new();
+        let mut q = TransferQueue::lock(queue)?;
+        for (id, job, poll, check) in updates {
+            let transfer = match q.transfers.iter_mut().find(|t| t.id == id && t.state == TransferState::Running && t.job == Some(job)) {
+                Some(transfer) => transfer,
+                // paused or cancelled while the daemon was polled
+                None => continue
+            };
+            match poll {
+                Ok((status, stats)) => {
+                    if let Some(stats) = stats {
+                        transfer.bytes = stats.bytes;
+                        transfer.total_bytes = stats.total_bytes;
+                        events.push(Remit::TransferEvent::Progress(Remit::TransferProgress{id: transfer.id, source: Remit::TransferSource::Queue, bytes: stats.bytes,
+                                                                     total_bytes: stats.total_bytes, speed: stats.speed, eta: stats.eta}));
+                    }
+                    if status.finished {
+                        transfer.job = None;
+                        transfer.state = if status.success { TransferState::Done } else { TransferState::Failed };
+                        transfer.error = status.error;
+                        match check {
+                            None | Some(Ok(true)) => {},
+                            Some(Ok(false)) if transfer.retries < max_retries => {
+                                transfer.retries += 1;
+                                transfer.state = TransferState::Queued;
+                            },
+                            Some(Ok(false)) => {
+                                transfer.state = TransferState::Failed;
+                                transfer.error = format!("{} does not match its source after {} attempts", transfer.filename, transfer.retries + 1);
+                            },
+                            Some(Err(e)) => {
+                                transfer.state = TransferState::Failed;
+                                transfer.error = format!("verification failed: {}", e);
+                            }
+                        }
+                    }
+                },
+                Err(e) => {
+                    transfer.job = None;
+                    transfer.state = TransferState::Failed;
+                    transfer.error = e.to_string();
+                }
+            }
+            if transfer.state.is_finished() {
+                events.push(Remit::TransferEvent::Finished(Remit::TransferResult{id: transfer.id, source: Remit::TransferSource::Queue, success: transfer.state == TransferState::Done,
+                                                                                 error: transfer.error.clone()}));
+            }
+        }
+
+        let running = q.transfers.iter().filter(|t| t.state == TransferState::Running).count();
+        let mut queued: Vec<Transfer> = q.transfers.iter().filter(|t| t.state == TransferState::Queued).cloned().collect();
+        queued.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.id.cmp(&b.id)));
+        queued.truncate(q.max_running.saturating_sub(running));
+        let listener = q.listener.clone();
+        drop(q);
+
+        let mut started = Vec::new();
+        if let Ok(client) = &client {
+            for transfer in queued {
+                started.push((transfer.id, client.copy_file(&transfer.source, &transfer.filename, &transfer.destination, &transfer.filename)));
+            }
+        }
+        let mut orphans = Vec::new();
+        if started.len() > 0 {
+            let mut q = TransferQueue::lock(queue)?;
+            for (id, result) in started {
+                let transfer = match q.transfers.iter_mut().find(|t| t.id == id && t.state == TransferState::Queued) {
+                    Some(transfer) => transfer,
+                    None => {
+                        // paused or cancelled while the job was starting
+                        if let Ok(job) = result {
+                            orphans.push(job);
+                        }
+                        continue;
+                    }
+                };
+                match result {
+                    Ok(job) => {
+                        transfer.job = Some(job);
+                        transfer.state = TransferState::Running;
+                        transfer.error.clear();
+                    },
+                    Err(e) => {
+                        transfer.state = TransferState::Failed;
+                        transfer.error = e.to_string();
+                        events.push(Remit::TransferEvent::Finished(Remit::TransferResult{id: transfer.id, source: Remit::TransferSource::Queue, success: false,
+                                                                                         error: transfer.error.clone()}));
+                    }
+                }
+            }
+        }
+        if let Ok(client) = &client {
+            for job in orphans {
+                let _r = client.stop_job(job);
+            }
+        }
+        if let Some(listener) = listener {
+            for event in events {
+                listener(event);
+            }
+        }
+        return Ok(());
+    }
+}
+
+}
@@ -0,0 +1,186 @@
+//! The trash manager gives remote deletions a safety net. Instead of removing a file with `rm`, the file is moved into a trash directory on
+//! the server where it can later be restored or purged. Each configuration has its own trash directory and every deletion gets its own
+//! entry named after the time it was made in milliseconds and a counter, so deletions in the same millisecond don't share an entry:
+//! 
+//! ```
+//! $HOME/.remit-trash/<config name>/<id>/item   the deleted file or directory
+//! $HOME/.remit-trash/<config name>/<id>/meta   information needed to restore it
+//! ```
+//! 
+//! The meta file uses the same line based format as Remit configuration files:
+//! 
+//! ```
+//! path original remote path
+//! deleted seconds since the unix epoch
+//! undo true if the entry was only made so the deletion can be undone while the trash is disabled
+//! ```
+
+pub mod rustssh {
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::*;
+
+/// Counter added to trash ids so deletions made in the same millisecond get different entries
+static TRASH_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A deleted file or directory stored in the trash
+#[derive(Debug, Clone)]
+pub struct TrashItem {
+    /// Name of the entry in the trash directory
+    pub id: String,
+    /// Remote path the item was deleted from
+    pub original_path: String,
+    /// When the item was deleted in seconds since the unix epoch
+    pub deleted: u64,
+    /// True if the item was only trashed so its deletion could be undone. These are purged when the session ends
+    pub undo_only: bool
+}
+
+/// Moves remote files into a per configuration trash directory and restores or purges them
+pub struct TrashManager {
+    /// Name of the configuration the trash belongs to
+    config: String
+}
+
+#[allow(dead_code)]
+impl TrashManager {
+    /// Create a trash manager for a configuration
+    /// # Arguments
+    /// * `config` - Name of the configuration
+    pub fn new(config: String) -> TrashManager {
+        return TrashManager{config: config};
+    }
+
+    /// Get the remote trash directory of the configuration. Uses $HOME so it must be placed in double quotes rather than single
+    fn root(&self) -> String {
+        return format!("$HOME/.remit-trash/{}", self.config);
+    }
+
+    /// Get the current time in seconds since the unix epoch
+    fn now() -> u64 {
+        return SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    }
+
+    /// Move a remote file or directory into the trash. Returns the id of the new trash entry
+    /// 
+    /// The entry is created with `mkdir` rather than `mkdir -p`, so an id that already exists fails instead of nesting one item in another
+    /// # Arguments
+    /// * `ssh` - Connected session used to run the commands
+    /// * `remote_path` - Path of the file or directory to trash
+    /// * `undo_only` - The entry is only kept so the deletion can be undone, see [`TrashManager::purge_undo_only`]
+    pub fn trash(&self, ssh: &mut Remit::SessionManager, remote_path: &Remit::SystemPath, undo_only: bool) -> Result<String, IOError> {
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        let id = format!("{}-{}", millis, TRASH_COUNTER.fetch_add(1, Ordering::Relaxed));
+        let entry = format!("{}/{}", self.root(), id);
+        // only remove the entry on failure if this command created it
+        let output = ssh.run_command(format!("mkdir -p \"{}\" && mkdir \"{}\" && {{ printf 'path %s\\ndeleted %s\\nundo %s\\n' \"{}\" \"{}\" \"{}\" > \"{}/meta\" && mv \"{}\" \"{}/item\" && echo trashed || rm -rf \"{}\"; }}",
+                                             self.root(), entry, remote_path.get_path(), TrashManager::now(), undo_only, entry, remote_path.get_path(), entry, entry))?;
+        if !output.ends_with("trashed") {
+            return Err(IOError::new(IOErrorKind::Other, format!("Could not move {} to the trash", remote_path.get_path())));
+        }
+        return Ok(id);
+    }
+
+    /// List every item in the trash, oldest first
+    /// # Arguments
+    /// * `ssh` - Connected session used to run the commands
+    pub fn list(&self, ssh: &mut Remit::SessionManager) -> Result<Vec<TrashItem>, IOError> {
+        let output = ssh.run_command(format!("for d in \"{}\"/*/; do [ -f \"$d/meta\" ] && echo \"id $(basename \"$d\")\" && cat \"$d/meta\" && echo; done",
+                                             self.root()))?;
+        let mut items: Vec<TrashItem> = Vec::new();
+        for chunk in output.split("\n\n") {
+            let mut item = TrashItem{id: String::new(), original_path: String::new(), deleted: 0, undo_only: false};
+            for line in chunk.lines() {
+                let mut args = line.splitn(2, ' ');
+                let key = args.next().unwrap_or("");
+                let value = args.next().unwrap_or("").to_string();
+                match key {
+                    "id"=>item.id = value,
+                    "path"=>item.original_path = value,
+                    "deleted"=>item.deleted = value.parse::<u64>().unwrap_or(0),
+                    "undo"=>item.undo_only = value == "true",
+                    _=>{}
+                }
+            }
+            if item.id.len() > 0 {
+                items.push(item);
+            }
+        }
+        items.sort_by(|a, b| a.deleted.cmp(&b.deleted));
+        return Ok(items);
+    }
+
+    /// Move an item in the trash back to its original path. Fails if something already exists at that path. Returns the restored path
+    /// # Arguments
+    /// * `ssh` - Connected session used to run the commands
+    /// * `id` - Id of the trash entry
+    pub fn restore(&self, ssh: &mut Remit::SessionManager, id: &String) -> Result<String, IOError> {
+        let item = self.list(ssh)?.into_iter().find(|item| item.id == *id)
+                       .ok_or(IOError::new(IOErrorKind::NotFound, "Item not found in trash"))?;
+        let entry = format!("{}/{}", self.root(), item.id);
+        if ssh.run_command(format!("[ -e \"{}\" ] && echo exists", item.original_path))? == "exists" {
+            return Err(IOError::new(IOErrorKind::AlreadyExists, format!("{} already exists", item.original_path)));
+        }
+        let output = ssh.run_command(format!("mkdir -p \"$(dirname \"{}\")\" && mv \"{}/item\" \"{}\" && rm -rf \"{}\" && echo restored",
+                                             item.original_path, entry, item.original_path, entry))?;
+        if !output.ends_with("restored") {
+            return Err(IOError::new(IOErrorKind::Other, format!("Could not restore {}", item.original_path)));
+        }
+        return Ok(item.original_path);
+    }
+
+    /// Permanently delete an item from the trash. If no id is given the whole trash is emptied
+    /// # Arguments
+    /// * `ssh` - Connected session used to run the commands
+    /// * `id` - Id of the trash entry to delete
+    pub fn purge(&self, ssh: &mut Remit::SessionManager, id: Option<String>) -> Result<(), IOError> {
+        match id {
+            Some(id) => {
+                if id.len() == 0 || id.contains('/') || id.starts_with('.') {
+                    return Err(IOError::new(IOErrorKind::InvalidInput, "Invalid trash id"));
+                }
+                ssh.run_command(format!("rm -rf \"{}/{}\"", self.root(), id))?;
+            },
+            None => {
+                ssh.run_command(format!("rm -rf \"{}\"", self.root()))?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Permanently delete every item that has been in the trash longer than the retention period. Returns the number of items removed
+    /// # Arguments
+    /// * `ssh` - Connected session used to run the commands
+    /// * `retention_days` - Number of days to keep items. 0 keeps items forever
+    pub fn purge_expired(&self, ssh: &mut Remit::SessionManager, retention_days: u64) -> Result<usize, IOError> {
+        if retention_days == 0 {
+            return Ok(0);
+        }
+        let cutoff = TrashManager::now().saturating_sub(retention_days * 24 * 60 * 60);
+        let mut removed = 0usize;
+        for item in self.list(ssh)? {
+            if item.deleted < cutoff {
+                self.purge(ssh, Some(item.id))?;
+                removed += 1;
+            }
+        }
+        return Ok(removed);
+    }
+
+    /// Permanently delete every item that was only trashed so its deletion could be undone. These are left behind when a session
+    /// ends without purging them, e.g. after losing the connection. Returns the number of items removed
+    /// # Arguments
+    /// * `ssh` - Connected session used to run the commands
+    pub fn purge_undo_only(&self, ssh: &mut Remit::SessionManager) -> Result<usize, IOError> {
+        let mut removed = 0usize;
+        for item in self.list(ssh)? {
+            if item.undo_only {
+                self.purge(ssh, Some(item.id))?;
+                removed += 1;
+            }
+        }
+        return Ok(removed);
+    }
+}
+
+}
@@ -22,7 +22,8 @@ class App extends Component{
                   configs: {},
                   openSaveManager: false,
                   saveManagerData: undefined,
-                  rclone_exists: true};
+                  rclone_exists: true,
+                  rclone_error: ""};
   }
   /**
    * Set the logged in state to true
@@ -63,13 +64,14 @@ class App extends Component{
     this.checkForRclone()
       .then((r) => {
         console.log(r);
-        this.setState({rclone_exists: r});
+        this.setState({rclone_exists: r.supported === "true", rclone_error: r.error});
       });
   }
 
   /**
    * Make call to the backend to check if a valid rclone exists
-   * @returns {bool} True if a valid rclone exe exists, otherwise false
+   * @returns {Object} supported is "true" if a valid rclone exe exists and is new enough, otherwise error says why. Also has
+   * the path it was found at and its version
    * @access private
    */
   checkForRclone() {
@@ -91,7 +93,7 @@ class App extends Component{
 
     let page = <Login openSaveManagerHandler={this.openSaveManager.bind(this)} loggedInCallback={this.login.bind(this)}/>
     if (!this.state.rclone_exists) {
-      page = <OkDialog show={true} title="rclone not found!" text="rclone-x86_64-pc-windows-msvc.exe not found. Please download rclone, rename it accordingly and place it in the directory"
+      page = <OkDialog show={true} title="rclone can't be used!" text={this.state.rclone_error}
                 onClick={()=>this.setState({logged:false})}/>;
     } else if (this.state.saveManagerData) {
         page = <SaveManager password={this.state.saveManagerData.password} username={this.state.saveManagerData.username} 